@@ -0,0 +1,91 @@
+use crate::constants::*;
+use crate::numerology::*;
+use crate::rrc_pucch::BwpR;
+use serde_derive::{Deserialize, Serialize};
+
+// 38.211, 4.4.4.4, the largest resource grid N_grid^size
+const MAX_NUM_RB: u32 = 275;
+
+// 38.211, 4.4.5, bandwidth part, PRB n_PRB of the BWP is CRB n_PRB + N_BWP^start
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Bwp {
+    pub start_crb: u32, // N_BWP^start
+    pub num_rb: u32,    // N_BWP^size
+    pub numerology: Numerology,
+}
+
+impl Bwp {
+    pub fn new(start_crb: u32, num_rb: u32, numerology: Numerology) -> Self {
+        assert!(num_rb > 0 && num_rb <= MAX_NUM_RB, "invalid BWP size {}!", num_rb);
+        assert!(start_crb + num_rb <= MAX_NUM_RB, "BWP start CRB {} with {} RBs exceeds the grid!", start_crb, num_rb);
+        Bwp { start_crb, num_rb, numerology }
+    }
+
+    // PRBs [start_prb, start_prb + num_rb) within the BWP
+    pub fn contains(&self, start_prb: u32, num_rb: u32) -> bool {
+        start_prb + num_rb <= self.num_rb
+    }
+
+    pub fn prb_to_crb(&self, prb: u32) -> u32 {
+        assert!(prb < self.num_rb, "PRB {} out of BWP of {} RBs!", prb, self.num_rb);
+        prb + self.start_crb
+    }
+
+    #[allow(dead_code)]
+    pub fn crb_to_prb(&self, crb: u32) -> u32 {
+        assert!(crb >= self.start_crb && crb < self.start_crb + self.num_rb, "CRB {} out of BWP!", crb);
+        crb - self.start_crb
+    }
+
+    // 38.211, 4.4.4.3, frequency of subcarrier k of the PRB, subcarrier 0 of CRB 0 is at point A
+    pub fn sc_freq_hz(&self, prb: u32, k: u32, point_a_hz: f64) -> f64 {
+        let sc = self.prb_to_crb(prb) * NUM_SC_PER_RB + k;
+        point_a_hz + sc as f64 * self.numerology.scs_hz()
+    }
+
+    // center frequency of the PRB, between subcarrier 5 and 6
+    #[allow(dead_code)]
+    pub fn prb_center_freq_hz(&self, prb: u32, point_a_hz: f64) -> f64 {
+        self.sc_freq_hz(prb, 0, point_a_hz) + (NUM_SC_PER_RB - 1) as f64 / 2f64 * self.numerology.scs_hz()
+    }
+}
+
+impl Default for Bwp {
+    fn default() -> Self {
+        Bwp::new(0, 106, Numerology::default())
+    }
+}
+
+impl From<&BwpR> for Bwp {
+    fn from(bwp_rrc: &BwpR) -> Self {
+        let generic_parameters = &bwp_rrc.generic_parameters;
+        let numerology = Numerology::from_scs(generic_parameters.subcarrier_spacing, generic_parameters.cyclic_prefix);
+        Bwp::new(bwp_rrc.start_crb, bwp_rrc.num_rb, numerology)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pucch::PucchConfig;
+    use crate::test_util::*;
+
+    #[test]
+    fn prb_to_freq() {
+        let bwp = Bwp::new(10, 51, Numerology::new(1, CyclicPrefix::Normal));
+        assert!(bwp.contains(41, 10) && !bwp.contains(42, 10));
+        assert_eq!((bwp.prb_to_crb(0), bwp.crb_to_prb(60)), (10, 50));
+        // CRB 12, subcarrier 3 at 30 kHz
+        assert_eq!(bwp.sc_freq_hz(2, 3, 3.5e9), 3.5e9 + (12 * 12 + 3) as f64 * 30e3);
+        assert_eq!(bwp.prb_center_freq_hz(0, 0f64), 120f64 * 30e3 + 165e3);
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds the BWP")]
+    fn hopping_prb_out_of_bwp() {
+        let mut rrc_pucch_config = test_pucch_config_rrc(&[]);
+        // format 3 of 10 RBs hops to PRB 40
+        rrc_pucch_config.bwp.as_mut().unwrap().num_rb = 49;
+        let _: PucchConfig = rrc_pucch_config.into();
+    }
+}
@@ -0,0 +1,386 @@
+use crate::complex::*;
+use crate::constants::*;
+use crate::numerology::*;
+use crate::pucch_tx::PucchSymbol;
+use serde_derive::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+// number of sinusoids per tap of the sum-of-sinusoids Doppler model
+const NUM_SINUSOIDS: usize = 16;
+const MAX_NUM_RX: u32 = 4;
+
+// 38.901, Table 7.7.2-1, TDL-A, normalized delay and power in dB
+const TDL_A: [(f32, f32); 23] = [
+    (0.0000, -13.4),
+    (0.3819, 0.0),
+    (0.4025, -2.2),
+    (0.5868, -4.0),
+    (0.4610, -6.0),
+    (0.5375, -8.2),
+    (0.6708, -9.9),
+    (0.5750, -10.5),
+    (0.7618, -7.5),
+    (1.5375, -15.9),
+    (1.8978, -6.6),
+    (2.2242, -16.7),
+    (2.1718, -12.4),
+    (2.4942, -15.2),
+    (2.5119, -10.8),
+    (3.0582, -11.3),
+    (4.0810, -12.7),
+    (4.4579, -16.2),
+    (4.5695, -18.3),
+    (4.7966, -18.9),
+    (5.0066, -16.6),
+    (5.3043, -19.9),
+    (9.6586, -29.7),
+];
+
+// 38.901, Table 7.7.2-2, TDL-B
+const TDL_B: [(f32, f32); 23] = [
+    (0.0000, 0.0),
+    (0.1072, -2.2),
+    (0.2155, -4.0),
+    (0.2095, -3.2),
+    (0.2870, -9.8),
+    (0.2986, -1.2),
+    (0.3752, -3.4),
+    (0.5055, -5.2),
+    (0.3681, -7.6),
+    (0.3697, -3.0),
+    (0.5700, -8.9),
+    (0.5283, -9.0),
+    (1.1021, -4.8),
+    (1.2756, -5.7),
+    (1.5474, -7.5),
+    (1.7842, -1.9),
+    (2.0169, -7.6),
+    (2.8294, -12.2),
+    (3.0219, -9.8),
+    (3.6187, -11.4),
+    (4.1067, -14.9),
+    (4.2790, -9.2),
+    (4.7834, -11.3),
+];
+
+// 38.901, Table 7.7.2-3, TDL-C, tap 6 is the table value and not 2 / pi
+#[allow(clippy::approx_constant)]
+const TDL_C: [(f32, f32); 24] = [
+    (0.0000, -4.4),
+    (0.2099, -1.2),
+    (0.2219, -3.5),
+    (0.2329, -5.2),
+    (0.2176, -2.5),
+    (0.6366, 0.0),
+    (0.6448, -2.2),
+    (0.6560, -3.9),
+    (0.6584, -7.4),
+    (0.7935, -7.1),
+    (0.8213, -10.7),
+    (0.9336, -11.1),
+    (1.2285, -5.1),
+    (1.3083, -6.8),
+    (2.1704, -8.7),
+    (2.7105, -13.2),
+    (4.2589, -13.9),
+    (4.6003, -13.9),
+    (5.4902, -15.8),
+    (5.6077, -17.1),
+    (6.3065, -16.0),
+    (6.6374, -15.7),
+    (7.0427, -21.6),
+    (8.6523, -22.8),
+];
+
+// xorshift64* generator, the channel realizations and the noise are reproducible from the seed
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // splitmix64 of the seed, so that close seeds give unrelated sequences and the state is never 0
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        Rng { state: (z ^ (z >> 31)).max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    // uniform in (0, 1)
+    pub fn uniform(&mut self) -> f64 {
+        ((self.next_u64() >> 11) as f64 + 0.5) / (1u64 << 53) as f64
+    }
+
+    // circularly-symmetric complex Gaussian of variance var, Box-Muller
+    pub fn complex_gaussian(&mut self, var: f32) -> Complex {
+        let (u1, u2) = (self.uniform(), self.uniform());
+        let amp = (-(var as f64) * u1.ln()).sqrt();
+        let phase = 2f64 * PI * u2;
+        Complex::new((amp * phase.cos()) as f32, (amp * phase.sin()) as f32)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FadingProfile {
+    Awgn,
+    TdlA,
+    TdlB,
+    TdlC,
+}
+
+impl FadingProfile {
+    fn taps(&self) -> &'static [(f32, f32)] {
+        match self {
+            FadingProfile::Awgn => &[(0f32, 0f32)],
+            FadingProfile::TdlA => &TDL_A,
+            FadingProfile::TdlB => &TDL_B,
+            FadingProfile::TdlC => &TDL_C,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ChannelConfig {
+    pub profile: FadingProfile,
+    pub delay_spread_ns: f32, // 38.901, 7.7.3, the normalized delays are scaled by the delay spread
+    pub max_doppler_hz: f32,
+    pub num_rx: u32,
+    pub correlation: f32,      // alpha of the receive antenna correlation, 0 for uncorrelated
+    pub snr_db: f32,           // per RE and per receive antenna, for unit transmit and average channel power
+    pub timing_offset_ns: f32, // late arrival of the whole power delay profile
+    pub cfo_hz: f32,
+}
+
+impl Default for ChannelConfig {
+    fn default() -> Self {
+        ChannelConfig {
+            profile: FadingProfile::Awgn,
+            delay_spread_ns: 0f32,
+            max_doppler_hz: 0f32,
+            num_rx: 1,
+            correlation: 0f32,
+            snr_db: 10f32,
+            timing_offset_ns: 0f32,
+            cfo_hz: 0f32,
+        }
+    }
+}
+
+// Rayleigh fading of one tap and one receive antenna, sum of sinusoids with random arrival angles and phases
+#[derive(Debug, Clone)]
+struct SumOfSinusoids {
+    doppler_hz: Vec<f64>,
+    phase: Vec<f64>,
+}
+
+impl SumOfSinusoids {
+    fn new(max_doppler_hz: f32, rng: &mut Rng) -> Self {
+        let doppler_hz = (0..NUM_SINUSOIDS).map(|_| max_doppler_hz as f64 * (2f64 * PI * rng.uniform()).cos()).collect();
+        let phase = (0..NUM_SINUSOIDS).map(|_| 2f64 * PI * rng.uniform()).collect();
+        SumOfSinusoids { doppler_hz, phase }
+    }
+
+    // unit average power
+    fn gain(&self, t: f64) -> Complex {
+        let scale = 1f32 / (NUM_SINUSOIDS as f32).sqrt();
+        self.doppler_hz
+            .iter()
+            .zip(&self.phase)
+            .map(|(&fd, &phase)| Complex::expj(((2f64 * PI * fd * t + phase) % (2f64 * PI)) as f32))
+            .sum::<Complex>()
+            * scale
+    }
+}
+
+// 38.901, 7.7.2, TDL channel with the 38.104, Annex G receive antenna correlation, followed by AWGN
+pub struct Channel {
+    config: ChannelConfig,
+    numerology: Numerology,
+    delay_s: Vec<f64>,
+    amplitude: Vec<f32>,
+    fading: Vec<Vec<SumOfSinusoids>>, // [tap][antenna]
+    correlation: Vec<Vec<f32>>,       // lower triangular square root of the correlation matrix
+    rng: Rng,
+}
+
+impl Channel {
+    pub fn new(config: &ChannelConfig, numerology: Numerology, seed: u64) -> Self {
+        assert!(
+            config.num_rx.is_power_of_two() && config.num_rx <= MAX_NUM_RX,
+            "invalid number of receive antennas {}!",
+            config.num_rx
+        );
+        assert!((0f32..=1f32).contains(&config.correlation), "invalid antenna correlation {}!", config.correlation);
+        let mut rng = Rng::new(seed);
+        let taps = config.profile.taps();
+        let delay_s = taps.iter().map(|&(delay, _)| delay as f64 * config.delay_spread_ns as f64 * 1e-9).collect();
+        let total_power = taps.iter().map(|&(_, power_db)| 10f32.powf(power_db / 10f32)).sum::<f32>();
+        let amplitude = taps.iter().map(|&(_, power_db)| (10f32.powf(power_db / 10f32) / total_power).sqrt()).collect();
+        let fading = taps
+            .iter()
+            .map(|_| (0..config.num_rx).map(|_| SumOfSinusoids::new(config.max_doppler_hz, &mut rng)).collect())
+            .collect();
+        Channel {
+            config: *config,
+            numerology,
+            delay_s,
+            amplitude,
+            fading,
+            correlation: cholesky(&correlation_matrix(config.num_rx as usize, config.correlation)),
+            rng,
+        }
+    }
+
+    pub fn noise_var(&self) -> f32 {
+        10f32.powf(-self.config.snr_db / 10f32)
+    }
+
+    // frequency response of all the receive antennas on subcarrier sc of the BWP at time t
+    // the timing offset is a linear phase over the subcarriers, the CFO a phase rotation over time without ICI
+    pub fn freq_response(&self, sc: u32, t: f64) -> Vec<Complex> {
+        let freq_hz = sc as f64 * self.numerology.scs_hz();
+        let num_rx = self.config.num_rx as usize;
+        let offset_phase = -freq_hz * self.config.timing_offset_ns as f64 * 1e-9 + self.config.cfo_hz as f64 * t;
+        let offset = Complex::expj((2f64 * PI * (offset_phase % 1f64)) as f32);
+        let mut h = vec![Complex::ZERO; num_rx];
+        for ((&delay_s, &amplitude), fading) in self.delay_s.iter().zip(&self.amplitude).zip(&self.fading) {
+            let delay_phase = Complex::expj((-2f64 * PI * ((freq_hz * delay_s) % 1f64)) as f32) * amplitude;
+            let z = fading
+                .iter()
+                .map(|fading| if self.config.profile == FadingProfile::Awgn { Complex::ONE } else { fading.gain(t) })
+                .collect::<Vec<_>>();
+            for (h, row) in h.iter_mut().zip(&self.correlation) {
+                *h += row.iter().zip(&z).map(|(&c, &z)| z * c).sum::<Complex>() * delay_phase;
+            }
+        }
+        h.iter().map(|&h| h * offset).collect()
+    }
+
+    // start time of symbol sym of the slot, slot counts from the start of the simulation
+    fn sym_time(&self, slot: u32, sym: u32) -> f64 {
+        (slot as f64 * self.numerology.num_sym_per_slot() as f64 + sym as f64) * self.numerology.sym_duration_s()
+    }
+
+    // fading on the PUCCH symbols of the slot without noise, the output is indexed by [antenna][symbol]
+    pub fn fade(&self, tx: &[PucchSymbol], slot: u32) -> Vec<Vec<PucchSymbol>> {
+        let mut rx = vec![Vec::with_capacity(tx.len()); self.config.num_rx as usize];
+        for symbol in tx {
+            let t = self.sym_time(slot, symbol.sym);
+            let mut data = vec![Vec::with_capacity(symbol.data.len()); rx.len()];
+            for (k, &x) in symbol.data.iter().enumerate() {
+                let h = self.freq_response(symbol.start_prb * NUM_SC_PER_RB + k as u32, t);
+                data.iter_mut().zip(h).for_each(|(data, h)| data.push(x * h));
+            }
+            for (rx, data) in rx.iter_mut().zip(data) {
+                rx.push(PucchSymbol { sym: symbol.sym, start_prb: symbol.start_prb, data });
+            }
+        }
+        rx
+    }
+
+    // fading and noise on the PUCCH symbols of the slot, the output is indexed by [antenna][symbol]
+    pub fn apply(&mut self, tx: &[PucchSymbol], slot: u32) -> Vec<Vec<PucchSymbol>> {
+        let noise_var = self.noise_var();
+        let mut rx = self.fade(tx, slot);
+        // the noise is drawn RE by RE over the antennas
+        for (s, symbol) in tx.iter().enumerate() {
+            for k in 0..symbol.data.len() {
+                rx.iter_mut().for_each(|rx| rx[s].data[k] += self.rng.complex_gaussian(noise_var));
+            }
+        }
+        rx
+    }
+}
+
+// 38.104, G.2.3, R_ij = alpha^(((i - j) / (N - 1))^2)
+fn correlation_matrix(num_rx: usize, alpha: f32) -> Vec<Vec<f32>> {
+    (0..num_rx)
+        .map(|i| {
+            (0..num_rx)
+                .map(|j| {
+                    let d = i.abs_diff(j) as f32 / (num_rx.max(2) - 1) as f32;
+                    if i == j {
+                        1f32
+                    } else {
+                        alpha.powf(d * d)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// lower triangular L with L L^T = r, the semi-definite r of the full correlation is clamped to zero pivots
+fn cholesky(r: &[Vec<f32>]) -> Vec<Vec<f32>> {
+    let n = r.len();
+    let mut l = vec![vec![0f32; n]; n];
+    for i in 0..n {
+        for j in 0..=i {
+            let sum = r[i][j] - (0..j).map(|k| l[i][k] * l[j][k]).sum::<f32>();
+            l[i][j] = if i == j {
+                sum.max(0f32).sqrt()
+            } else if l[j][j] > 0f32 {
+                sum / l[j][j]
+            } else {
+                0f32
+            };
+        }
+    }
+    l
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx_symbols(num_sym: u32, num_sc: usize) -> Vec<PucchSymbol> {
+        (0..num_sym).map(|sym| PucchSymbol { sym, start_prb: 3, data: vec![Complex::ONE; num_sc] }).collect()
+    }
+
+    #[test]
+    fn awgn() {
+        let config = ChannelConfig { num_rx: 2, snr_db: 3f32, ..Default::default() };
+        let numerology = Numerology::default();
+        let rx = Channel::new(&config, numerology, 7).apply(&tx_symbols(14, 1200), 0);
+        assert_eq!(rx, Channel::new(&config, numerology, 7).apply(&tx_symbols(14, 1200), 0));
+        assert_ne!(rx, Channel::new(&config, numerology, 8).apply(&tx_symbols(14, 1200), 0));
+        for rx in &rx {
+            let noise = rx.iter().flat_map(|symbol| &symbol.data).map(|&y| (y - Complex::ONE).norm_sqr()).collect::<Vec<_>>();
+            let noise_var = noise.iter().sum::<f32>() / noise.len() as f32;
+            assert!((noise_var / 10f32.powf(-0.3) - 1f32).abs() < 0.05, "noise variance {}", noise_var);
+        }
+    }
+
+    #[test]
+    fn tdl_power_and_correlation() {
+        let numerology = Numerology::new(1, CyclicPrefix::Normal);
+        for profile in [FadingProfile::TdlA, FadingProfile::TdlB, FadingProfile::TdlC] {
+            let config = ChannelConfig {
+                profile,
+                delay_spread_ns: 300f32,
+                max_doppler_hz: 100f32,
+                num_rx: 4,
+                correlation: 0.9,
+                snr_db: 0f32,
+                ..Default::default()
+            };
+            let (mut power, mut cross) = (vec![0f32; 4], Complex::ZERO);
+            let num_drops = 2000;
+            for seed in 0..num_drops {
+                let h = Channel::new(&config, numerology, seed).freq_response(100, 1e-3);
+                h.iter().zip(power.iter_mut()).for_each(|(h, power)| *power += h.norm_sqr() / num_drops as f32);
+                cross += h[0] * h[3].conj() * (1f32 / num_drops as f32);
+            }
+            assert!(power.iter().all(|&power| (power - 1f32).abs() < 0.1), "{:?} power {:?}", profile, power);
+            assert!((cross.abs() - 0.9).abs() < 0.1, "{:?} correlation {}", profile, cross.abs());
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::complex::*;
+use serde_derive::{Deserialize, Serialize};
+
+// diagonal loading relative to the average noise power, keeps the IRC inverse stable on a few DMRS
+const DIAGONAL_LOADING: f32 = 1e-3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Combining {
+    Mrc, // maximum-ratio combining, the noise of the antennas is taken as uncorrelated
+    Irc, // interference rejection combining with the spatial covariance of the DMRS residuals
+}
+
+// spatial covariance of the channel estimation residuals, R_ij = sum e_i * conj(e_j) / (N - num_est)
+#[derive(Debug, Clone)]
+pub struct Covariance {
+    sum: Vec<Vec<Complex>>,
+    num_re: usize,
+    num_est: usize,
+}
+
+// w = R^-1 for IRC, the inverse of the diagonal of R for MRC
+#[derive(Debug, Clone)]
+pub struct Combiner {
+    w: Vec<Vec<Complex>>,
+}
+
+impl Covariance {
+    pub fn new(num_rx: usize) -> Self {
+        Covariance { sum: vec![vec![Complex::ZERO; num_rx]; num_rx], num_re: 0, num_est: 0 }
+    }
+
+    // residual of one RE, one entry per receive antenna
+    pub fn add(&mut self, e: &[Complex]) {
+        for (row, &e_i) in self.sum.iter_mut().zip(e) {
+            row.iter_mut().zip(e).for_each(|(sum, &e_j)| *sum += e_i * e_j.conj());
+        }
+        self.num_re += 1;
+    }
+
+    // one channel coefficient was estimated from the added REs, which takes one degree of freedom
+    pub fn add_estimate(&mut self) {
+        self.num_est += 1;
+    }
+
+    fn num_dof(&self) -> f32 {
+        self.num_re.saturating_sub(self.num_est).max(1) as f32
+    }
+
+    pub fn num_rx(&self) -> usize {
+        self.sum.len()
+    }
+
+    pub fn noise_var(&self, antenna: usize) -> f32 {
+        (self.sum[antenna][antenna].re / self.num_dof()).max(f32::MIN_POSITIVE)
+    }
+
+    pub fn combiner(&self, combining: Combining) -> Combiner {
+        let num_rx = self.num_rx();
+        let w = match combining {
+            Combining::Mrc => (0..num_rx)
+                .map(|i| (0..num_rx).map(|j| if i == j { Complex::ONE / self.noise_var(i) } else { Complex::ZERO }).collect())
+                .collect(),
+            Combining::Irc => {
+                let loading = DIAGONAL_LOADING * (0..num_rx).map(|i| self.noise_var(i)).sum::<f32>() / num_rx as f32;
+                let mut r =
+                    self.sum.iter().map(|row| row.iter().map(|&r| r / self.num_dof()).collect()).collect::<Vec<Vec<_>>>();
+                (0..num_rx).for_each(|i| r[i][i] += Complex::new(loading, 0f32));
+                inverse(r)
+            }
+        };
+        Combiner { w }
+    }
+}
+
+impl Combiner {
+    // r = h * x + n on the receive antennas, the output x is normalized to unit gain with the noise variance 1 / g,
+    // g = h^H * W * h is the SINR after combining
+    pub fn combine(&self, h: &[Complex], r: &[Complex]) -> (Complex, f32) {
+        let u = self.w.iter().map(|row| row.iter().zip(h).map(|(&w, &h)| w * h).sum::<Complex>()).collect::<Vec<_>>();
+        let g = h.iter().zip(&u).map(|(&h, &u)| (h.conj() * u).re).sum::<f32>().max(f32::MIN_POSITIVE);
+        let z = u.iter().zip(r).map(|(&u, &r)| u.conj() * r).sum::<Complex>();
+        (z / g, g)
+    }
+}
+
+// 10 * log10 of the average channel power over the noise variance
+pub fn sinr_db(channel_power: f32, noise_var: f32) -> f32 {
+    10f32 * (channel_power.max(f32::MIN_POSITIVE) / noise_var).log10()
+}
+
+fn complex_div(a: Complex, b: Complex) -> Complex {
+    a * b.conj() / b.norm_sqr().max(f32::MIN_POSITIVE)
+}
+
+// Gauss-Jordan elimination with partial pivoting, for the few receive antennas
+fn inverse(mut a: Vec<Vec<Complex>>) -> Vec<Vec<Complex>> {
+    let n = a.len();
+    let mut inv =
+        (0..n).map(|i| (0..n).map(|j| if i == j { Complex::ONE } else { Complex::ZERO }).collect()).collect::<Vec<Vec<_>>>();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].norm_sqr().total_cmp(&a[j][col].norm_sqr())).unwrap();
+        a.swap(col, pivot);
+        inv.swap(col, pivot);
+        let p = a[col][col];
+        a[col].iter_mut().for_each(|x| *x = complex_div(*x, p));
+        inv[col].iter_mut().for_each(|x| *x = complex_div(*x, p));
+        for row in (0..n).filter(|&row| row != col) {
+            let f = a[row][col];
+            for k in 0..n {
+                let (a_col, inv_col) = (a[col][k], inv[col][k]);
+                a[row][k] -= f * a_col;
+                inv[row][k] -= f * inv_col;
+            }
+        }
+    }
+    inv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn irc_rejects_interference() {
+        // desired channel h, interferer channel v, both on 2 antennas with small noise
+        let h = [Complex::ONE, Complex::expj(1.0)];
+        let v = [Complex::new(3f32, 0f32), Complex::expj(-0.5) * 3f32];
+        let mut cov = Covariance::new(2);
+        for n in 0..64 {
+            let i = Complex::expj(n as f32 * 2.3);
+            let noise = [Complex::expj(n as f32 * 0.7) * 0.01, Complex::expj(n as f32 * 1.9) * 0.01];
+            cov.add(&[v[0] * i + noise[0], v[1] * i + noise[1]]);
+        }
+        let r = [h[0] + v[0], h[1] + v[1]];
+        let (x_irc, g_irc) = cov.combiner(Combining::Irc).combine(&h, &r);
+        let (x_mrc, g_mrc) = cov.combiner(Combining::Mrc).combine(&h, &r);
+        assert!((x_irc - Complex::ONE).abs() < 0.05, "IRC {}", x_irc);
+        assert!((x_mrc - Complex::ONE).abs() > 1f32, "MRC {}", x_mrc);
+        assert!(g_irc > 100f32 * g_mrc);
+    }
+}
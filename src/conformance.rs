@@ -0,0 +1,290 @@
+use crate::channel::*;
+use crate::combining::Combining;
+use crate::err::Error;
+use crate::link_sim::*;
+use crate::pucch::*;
+use crate::pucch_tx::PucchTxParams;
+use crate::read_config::load_config;
+use crate::rrc_pucch::*;
+use core::fmt;
+
+// the PUCCH resource of the test case, appended to the base config
+const CONFORMANCE_RESOURCE_ID: u32 = 127;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConformanceMetric {
+    AckMiss,
+    NackToAck,
+    DtxToAck,
+    Bler,
+}
+
+impl ConformanceMetric {
+    fn value(&self, point: &LinkSimPoint) -> f32 {
+        match self {
+            ConformanceMetric::AckMiss => point.ack_miss,
+            ConformanceMetric::NackToAck => point.nack_to_ack,
+            ConformanceMetric::DtxToAck => point.dtx_to_ack,
+            ConformanceMetric::Bler => point.bler,
+        }
+    }
+}
+
+impl fmt::Display for ConformanceMetric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ConformanceMetric::AckMiss => "ACK missed detection",
+            ConformanceMetric::NackToAck => "NACK to ACK",
+            ConformanceMetric::DtxToAck => "DTX to ACK",
+            ConformanceMetric::Bler => "UCI BLER",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// 38.104, 8.3, one PUCCH performance requirement, the channel is at the required SNR
+#[derive(Debug, Clone)]
+pub struct ConformanceTest {
+    pub name: &'static str,
+    pub start_prb: u32,
+    pub intra_slot_freq_hopping: IntraSlotFreqHopping,
+    pub format: PucchFormat,
+    pub o_harq: u32,
+    pub o_csi: u32,
+    pub channel: ChannelConfig,
+    pub dtx_threshold: f32,
+    pub requirements: Vec<(ConformanceMetric, f32)>, // the metric shall not exceed the limit
+}
+
+#[derive(Debug, Clone)]
+pub struct ConformanceResult {
+    pub name: &'static str,
+    pub snr_db: f32,
+    pub num_trials: u32,
+    pub measured: Vec<(ConformanceMetric, f32, f32)>, // (metric, measured, limit)
+}
+
+impl ConformanceResult {
+    pub fn pass(&self) -> bool {
+        self.measured.iter().all(|&(_, value, limit)| value <= limit)
+    }
+}
+
+impl fmt::Display for ConformanceResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let verdict = if self.pass() { "PASS" } else { "FAIL" };
+        writeln!(f, "{}, SNR {} dB, {} trials: {}", self.name, self.snr_db, self.num_trials, verdict)?;
+        for (metric, value, limit) in &self.measured {
+            writeln!(f, "    {}: {:.4} (limit {})", metric, value, limit)?;
+        }
+        Ok(())
+    }
+}
+
+// 38.104, Annex G, TDLC300-100 with 2 uncorrelated receive antennas
+fn tdlc300_100(snr_db: f32) -> ChannelConfig {
+    ChannelConfig {
+        profile: FadingProfile::TdlC,
+        delay_spread_ns: 300f32,
+        max_doppler_hz: 100f32,
+        num_rx: 2,
+        correlation: 0f32,
+        snr_db,
+        ..Default::default()
+    }
+}
+
+// 38.104, 8.3.2 to 8.3.6, normal CP, 30 kHz SCS, TDLC300-100 and 2 receive antennas
+pub fn conformance_tests() -> Vec<ConformanceTest> {
+    use ConformanceMetric::*;
+    let hopping = IntraSlotFreqHopping::Hopping { second_prb: 40 };
+    let no_hopping = IntraSlotFreqHopping::NoHopping;
+    let ack = vec![(AckMiss, 0.01), (DtxToAck, 0.01)];
+    vec![
+        ConformanceTest {
+            name: "8.3.2 format 0, 1 symbol, ACK missed detection",
+            start_prb: 0,
+            intra_slot_freq_hopping: no_hopping,
+            format: PucchFormat::Format0 { init_cyclic_shift: 0, num_sym: 1, start_sym: 13 },
+            o_harq: 1,
+            o_csi: 0,
+            channel: tdlc300_100(9.6),
+            dtx_threshold: 0.3,
+            requirements: ack.clone(),
+        },
+        ConformanceTest {
+            name: "8.3.2 format 0, 2 symbols, ACK missed detection",
+            start_prb: 0,
+            intra_slot_freq_hopping: hopping,
+            format: PucchFormat::Format0 { init_cyclic_shift: 0, num_sym: 2, start_sym: 12 },
+            o_harq: 1,
+            o_csi: 0,
+            channel: tdlc300_100(3.8),
+            dtx_threshold: 0.25,
+            requirements: ack.clone(),
+        },
+        ConformanceTest {
+            name: "8.3.3 format 1, NACK to ACK",
+            start_prb: 0,
+            intra_slot_freq_hopping: hopping,
+            format: PucchFormat::Format1 { init_cyclic_shift: 0, num_sym: 14, start_sym: 0, time_occ: 0 },
+            o_harq: 2,
+            o_csi: 0,
+            channel: tdlc300_100(-3.4),
+            dtx_threshold: 0.05,
+            requirements: vec![(NackToAck, 0.001), (DtxToAck, 0.01)],
+        },
+        ConformanceTest {
+            name: "8.3.3 format 1, ACK missed detection",
+            start_prb: 0,
+            intra_slot_freq_hopping: hopping,
+            format: PucchFormat::Format1 { init_cyclic_shift: 0, num_sym: 14, start_sym: 0, time_occ: 0 },
+            o_harq: 2,
+            o_csi: 0,
+            channel: tdlc300_100(-3.8),
+            dtx_threshold: 0.05,
+            requirements: vec![(AckMiss, 0.01)],
+        },
+        ConformanceTest {
+            name: "8.3.4 format 2, ACK missed detection",
+            start_prb: 0,
+            intra_slot_freq_hopping: no_hopping,
+            format: PucchFormat::Format2 { num_rb: 4, num_sym: 1, start_sym: 13 },
+            o_harq: 4,
+            o_csi: 0,
+            channel: tdlc300_100(6.1),
+            dtx_threshold: 0.55,
+            requirements: ack,
+        },
+        ConformanceTest {
+            name: "8.3.4 format 2, UCI BLER",
+            start_prb: 0,
+            intra_slot_freq_hopping: no_hopping,
+            format: PucchFormat::Format2 { num_rb: 4, num_sym: 1, start_sym: 13 },
+            o_harq: 0,
+            o_csi: 22,
+            channel: tdlc300_100(6.0),
+            dtx_threshold: 0.4,
+            requirements: vec![(Bler, 0.01)],
+        },
+        ConformanceTest {
+            name: "8.3.5 format 3, 14 symbols, UCI BLER",
+            start_prb: 0,
+            intra_slot_freq_hopping: hopping,
+            format: PucchFormat::Format3 { num_rb: 1, num_sym: 14, start_sym: 0 },
+            o_harq: 0,
+            o_csi: 16,
+            channel: tdlc300_100(1.2),
+            dtx_threshold: 0.4,
+            requirements: vec![(Bler, 0.01)],
+        },
+        ConformanceTest {
+            name: "8.3.5 format 3, 4 symbols, UCI BLER",
+            start_prb: 0,
+            intra_slot_freq_hopping: hopping,
+            format: PucchFormat::Format3 { num_rb: 1, num_sym: 4, start_sym: 10 },
+            o_harq: 0,
+            o_csi: 16,
+            channel: tdlc300_100(4.6),
+            dtx_threshold: 0.4,
+            requirements: vec![(Bler, 0.01)],
+        },
+        ConformanceTest {
+            name: "8.3.6 format 4, UCI BLER",
+            start_prb: 0,
+            intra_slot_freq_hopping: hopping,
+            format: PucchFormat::Format4 { num_sym: 14, occ_len: 2, occ_idx: 0, start_sym: 0 },
+            o_harq: 0,
+            o_csi: 22,
+            channel: tdlc300_100(2.2),
+            dtx_threshold: 0.4,
+            requirements: vec![(Bler, 0.01)],
+        },
+    ]
+}
+
+impl ConformanceTest {
+    // base_config_file is the PUCCH-Config of the cell, the test resource is added with QPSK for format 3/4
+    pub fn run(
+        &self,
+        base_config_file: &str,
+        params: PucchTxParams,
+        combining: Combining,
+        num_trials: u32,
+        seed: u64,
+    ) -> Result<ConformanceResult, Error> {
+        let mut pucch_config_rrc = load_config::<PucchConfigR>(base_config_file)?;
+        pucch_config_rrc.pucch_format3.pi2_bpsk = false;
+        pucch_config_rrc.pucch_format4.pi2_bpsk = false;
+        pucch_config_rrc.pucch_resource.get_or_insert_with(Vec::new).push(PucchResourceR {
+            pucch_resource_id: CONFORMANCE_RESOURCE_ID,
+            start_prb: self.start_prb,
+            intra_slot_freq_hopping: self.intra_slot_freq_hopping,
+            format: self.format,
+        });
+        let pucch_config: PucchConfig = pucch_config_rrc.into();
+
+        let link_sim_config = LinkSimConfig {
+            pucch_resource_id: CONFORMANCE_RESOURCE_ID,
+            o_harq: self.o_harq,
+            o_csi: self.o_csi,
+            channel: self.channel,
+            snr_db: vec![self.channel.snr_db],
+            num_trials,
+            combining,
+            dtx_threshold: self.dtx_threshold,
+            seed,
+        };
+        let point = LinkSim::new(&pucch_config, params, &link_sim_config).run()[0];
+        Ok(ConformanceResult {
+            name: self.name,
+            snr_db: point.snr_db,
+            num_trials,
+            measured: self.requirements.iter().map(|&(metric, limit)| (metric, metric.value(&point), limit)).collect(),
+        })
+    }
+}
+
+// runs all the test cases and prints the pass/fail report, one result per test case
+pub fn run_conformance(
+    base_config_file: &str,
+    params: PucchTxParams,
+    combining: Combining,
+    num_trials: u32,
+    seed: u64,
+) -> Result<Vec<ConformanceResult>, Error> {
+    let mut results = Vec::new();
+    for test in conformance_tests() {
+        let result = test.run(base_config_file, params, combining, num_trials, seed)?;
+        print!("{}", result);
+        results.push(result);
+    }
+    let num_fail = results.iter().filter(|result| !result.pass()).count();
+    println!("{} failed out of {} test cases", num_fail, results.len());
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    #[test]
+    fn conformance_report() {
+        let params = test_params(0);
+        let results = run_conformance(TEST_PUCCH_CONFIG_FILE, params, Combining::Mrc, 4, 1).unwrap();
+        let tests = conformance_tests();
+        assert_eq!(results.len(), tests.len());
+        for (result, test) in results.iter().zip(&tests) {
+            assert_eq!(result.name, test.name);
+            assert_eq!(result.measured.iter().map(|m| (m.0, m.2)).collect::<Vec<_>>(), test.requirements);
+        }
+        // every requirement is met far above the required SNR
+        let mut test = conformance_tests().remove(2);
+        test.channel.snr_db += 20f32;
+        let result = test.run(TEST_PUCCH_CONFIG_FILE, params, Combining::Mrc, 20, 1).unwrap();
+        assert_eq!(result.measured.len(), test.requirements.len());
+        assert!(format!("{}", result).starts_with(test.name));
+        assert!(result.pass());
+    }
+}
@@ -1,8 +1,10 @@
-pub const NUM_SYM_PER_SLOT: u32 = 14;
 pub const NUM_SC_PER_RB: u32 = 12;
 
 pub const BPSK_BITS: u32 = 1;
 pub const QPSK_BITS: u32 = 2;
+#[allow(dead_code)]
 pub const QAM16_BITS: u32 = 4;
+#[allow(dead_code)]
 pub const QAM64_BITS: u32 = 6;
+#[allow(dead_code)]
 pub const QAM256_BITS: u32 = 8;
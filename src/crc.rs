@@ -0,0 +1,51 @@
+use crate::math::*;
+
+// 38.212, 5.1, CRC calculation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CrcType {
+    Crc6,  // gCRC6(D) = D^6 + D^5 + 1
+    Crc11, // gCRC11(D) = D^11 + D^10 + D^9 + D^5 + 1
+}
+
+impl CrcType {
+    pub fn crc_len(&self) -> u32 {
+        match self {
+            CrcType::Crc6 => 6,
+            CrcType::Crc11 => 11,
+        }
+    }
+
+    // generator polynomial without the highest order term
+    fn poly(&self) -> u32 {
+        match self {
+            CrcType::Crc6 => (1 << 5) | 1,
+            CrcType::Crc11 => (1 << 10) | (1 << 9) | (1 << 5) | 1,
+        }
+    }
+}
+
+// parity bits p_0, ..., p_(L-1), a_0 is the highest order coefficient of a(D)
+pub fn crc_calc(bits: &[u8], crc_type: CrcType) -> Vec<u8> {
+    let crc_len = crc_type.crc_len();
+    let poly = crc_type.poly();
+    let reg = bits.iter().fold(0u32, |reg, &bit| {
+        let feedback = ((reg >> (crc_len - 1)) & 1) ^ bit as u32;
+        let reg = (reg << 1) & mask(0, crc_len);
+        if feedback == 1 {
+            reg ^ poly
+        } else {
+            reg
+        }
+    });
+    (0..crc_len).rev().map(|i| ((reg >> i) & 1) as u8).collect()
+}
+
+pub fn crc_attach(bits: &[u8], crc_type: CrcType) -> Vec<u8> {
+    let mut bits_with_crc = bits.to_vec();
+    bits_with_crc.extend(crc_calc(bits, crc_type));
+    bits_with_crc
+}
+
+pub fn crc_check(bits_with_crc: &[u8], crc_type: CrcType) -> bool {
+    crc_calc(bits_with_crc, crc_type).iter().all(|&bit| bit == 0)
+}
@@ -0,0 +1,83 @@
+use crate::complex::*;
+use std::f32::consts::PI;
+
+// X(k) = sum_n x(n) * e^(-j * 2 * pi * n * k / N), not normalized
+pub fn dft(x: &[Complex]) -> Vec<Complex> {
+    mixed_radix_dft(x, -1f32)
+}
+
+// x(n) = sum_k X(k) * e^(j * 2 * pi * n * k / N), not normalized
+pub fn idft(x: &[Complex]) -> Vec<Complex> {
+    mixed_radix_dft(x, 1f32)
+}
+
+// 38.211, 6.3.1.4 and 6.3.2.6.4, transform precoding sizes M_sc = 2^a * 3^b * 5^c
+pub fn is_dft_size(len: usize) -> bool {
+    let mut len = len;
+    for radix in [2, 3, 5] {
+        while len > 0 && len.is_multiple_of(radix) {
+            len /= radix;
+        }
+    }
+    len == 1
+}
+
+fn smallest_factor(len: usize) -> usize {
+    (2..).take_while(|i| i * i <= len).find(|&i| len.is_multiple_of(i)).unwrap_or(len)
+}
+
+// decimation in time by the smallest prime factor r of N, X(k) = sum_p W_N^(p * k) * Y_p(k mod (N / r))
+fn mixed_radix_dft(x: &[Complex], sign: f32) -> Vec<Complex> {
+    let len = x.len();
+    if len <= 1 {
+        return x.to_vec();
+    }
+
+    let radix = smallest_factor(len);
+    let sub_len = len / radix;
+    let sub_dft = (0..radix)
+        .map(|p| {
+            let sub_x = x.iter().skip(p).step_by(radix).copied().collect::<Vec<_>>();
+            if sub_len > 1 {
+                mixed_radix_dft(&sub_x, sign)
+            } else {
+                sub_x
+            }
+        })
+        .collect::<Vec<_>>();
+
+    (0..len)
+        .map(|k| {
+            sub_dft
+                .iter()
+                .enumerate()
+                .map(|(p, sub)| Complex::expj(sign * 2f32 * PI * ((p * k) % len) as f32 / len as f32) * sub[k % sub_len])
+                .sum()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive_dft(x: &[Complex]) -> Vec<Complex> {
+        let len = x.len();
+        (0..len)
+            .map(|k| {
+                x.iter().enumerate().map(|(n, &x)| Complex::expj(-2f32 * PI * ((n * k) % len) as f32 / len as f32) * x).sum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dft_match_naive() {
+        assert!(is_dft_size(12) && is_dft_size(180) && is_dft_size(1) && !is_dft_size(84) && !is_dft_size(0));
+        for len in [1, 2, 12, 36, 60, 75, 96, 128, 7, 84] {
+            let x = (0..len).map(|n| Complex::new((n as f32 * 0.37).sin(), (n as f32 * 1.3).cos())).collect::<Vec<_>>();
+            let y = dft(&x);
+            assert!(y.iter().zip(naive_dft(&x)).all(|(&a, b)| (a - b).abs() < 1e-3), "DFT size {}", len);
+            assert!(idft(&y).iter().zip(&x).all(|(&a, &b)| (a / len as f32 - b).abs() < 1e-4), "IDFT size {}", len);
+        }
+    }
+}
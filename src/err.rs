@@ -1,5 +1,7 @@
 use std::io;
 
+// the payloads are only read through Debug
+#[allow(dead_code)]
 #[derive(Debug)]
 pub enum Error {
     Io(io::Error),
@@ -0,0 +1,121 @@
+use crate::math::*;
+
+const GOLD_NC: usize = 1600;
+// bits produced per register update, limited by the feedback tap x(n + 3)
+const GOLD_STEP: u32 = 28;
+const GOLD_REG_LEN: u32 = 31;
+
+// 38.211, 5.2.1, pseudo-random sequence c(n), generated up to 28 bits per register update
+#[derive(Debug, Clone)]
+pub struct GoldSeq {
+    x1: u32, // x1(n + i) on bit i, 0 <= i < 31
+    x2: u32, // x2(n + i) on bit i, 0 <= i < 31
+}
+
+impl GoldSeq {
+    pub fn new(c_init: u32) -> Self {
+        let mut gold_seq = GoldSeq { x1: 1, x2: c_init & mask(0, GOLD_REG_LEN) };
+        gold_seq.skip(GOLD_NC);
+        gold_seq
+    }
+
+    // c(n), ..., c(n + num_bits - 1) on bit 0.., then advance n by num_bits (<= 28)
+    fn step(&mut self, num_bits: u32) -> u32 {
+        let c = (self.x1 ^ self.x2) & mask(0, num_bits);
+        // bit k is x(n + 31 + k), valid for k < 28
+        let x1_next = self.x1 ^ (self.x1 >> 3);
+        let x2_next = self.x2 ^ (self.x2 >> 1) ^ (self.x2 >> 2) ^ (self.x2 >> 3);
+        let shift = GOLD_REG_LEN - num_bits;
+        self.x1 = ((self.x1 >> num_bits) | (x1_next << shift)) & mask(0, GOLD_REG_LEN);
+        self.x2 = ((self.x2 >> num_bits) | (x2_next << shift)) & mask(0, GOLD_REG_LEN);
+        c
+    }
+
+    pub fn skip(&mut self, num_bits: usize) {
+        (0..num_bits / GOLD_STEP as usize).for_each(|_| {
+            self.step(GOLD_STEP);
+        });
+        self.step((num_bits % GOLD_STEP as usize) as u32);
+    }
+
+    // the next 32 bits, c(n) on bit 0
+    pub fn next_word(&mut self) -> u32 {
+        let low = self.step(GOLD_STEP);
+        let high = self.step(32 - GOLD_STEP);
+        low | (high << GOLD_STEP)
+    }
+
+    pub fn bits(&mut self, len: usize) -> Vec<u8> {
+        let mut bits = Vec::with_capacity(len.next_multiple_of(32));
+        while bits.len() < len {
+            let word = self.next_word();
+            bits.extend((0..32).map(|i| ((word >> i) & 1) as u8));
+        }
+        bits.truncate(len);
+        bits
+    }
+}
+
+// c(offset), ..., c(offset + len - 1)
+pub fn gold_seq(c_init: u32, offset: usize, len: usize) -> Vec<u8> {
+    let mut gold_seq = GoldSeq::new(c_init);
+    gold_seq.skip(offset);
+    gold_seq.bits(len)
+}
+
+// 38.211, 6.3.2.2.1, group hopping
+pub fn c_init_group_hopping(n_id: u32) -> u32 {
+    n_id / 30
+}
+
+// 38.211, 6.3.2.2.1, sequence hopping
+pub fn c_init_seq_hopping(n_id: u32) -> u32 {
+    (1 << 5) * (n_id / 30) + n_id % 30
+}
+
+// 38.211, 6.3.2.2.2, cyclic shift hopping
+pub fn c_init_cyclic_shift(n_id: u32) -> u32 {
+    n_id
+}
+
+// 38.211, 6.3.2.5.1 and 6.3.2.6.1, scrambling of PUCCH format 2/3/4
+pub fn c_init_pucch_scrambling(n_rnti: u32, n_id: u32) -> u32 {
+    ((n_rnti << 15) + n_id) & mask(0, GOLD_REG_LEN)
+}
+
+// 38.211, 6.4.1.3.2.1, DMRS of PUCCH format 2, sym is the symbol index l in the slot of N_symb^slot symbols
+pub fn c_init_pucch_dmrs(n_id_0: u32, num_sym_per_slot: u32, slot: u32, sym: u32) -> u32 {
+    let (n_id_0, num_sym_per_slot, slot, sym) = (n_id_0 as u64, num_sym_per_slot as u64, slot as u64, sym as u64);
+    let c_init = ((1 << 17) * (num_sym_per_slot * slot + sym + 1) * (2 * n_id_0 + 1) + 2 * n_id_0) % (1 << 31);
+    c_init as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // bit by bit reference of 38.211, 5.2.1
+    fn gold_seq_ref(c_init: u32, len: usize) -> Vec<u8> {
+        let (mut x1, mut x2) = (1u32, c_init);
+        let mut seq = Vec::with_capacity(len);
+        for n in 0..GOLD_NC + len {
+            if n >= GOLD_NC {
+                seq.push(((x1 ^ x2) & 1) as u8);
+            }
+            let x1_new = (x1 ^ (x1 >> 3)) & 1;
+            let x2_new = (x2 ^ (x2 >> 1) ^ (x2 >> 2) ^ (x2 >> 3)) & 1;
+            x1 = (x1 >> 1) | (x1_new << 30);
+            x2 = (x2 >> 1) | (x2_new << 30);
+        }
+        seq
+    }
+
+    #[test]
+    fn gold_seq_match_ref() {
+        for c_init in [0, 1, 0x1234, c_init_pucch_scrambling(0xffff, 1023), c_init_pucch_dmrs(1007, 14, 79, 13)] {
+            let seq = gold_seq_ref(c_init, 1000);
+            assert_eq!(gold_seq(c_init, 0, 1000), seq);
+            assert_eq!(gold_seq(c_init, 333, 500), &seq[333..833]);
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use crate::complex::*;
+use crate::constants::*;
+use crate::err::Error;
+use crate::pucch::*;
+use crate::pucch_tx::PucchSymbol;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// resource elements of one slot, symbol major, subcarrier k of symbol l at l * num_sc + k
+#[derive(Debug, Clone)]
+pub struct ResourceGrid {
+    num_rb: u32,
+    num_sym: u32,
+    re: Vec<Complex>,
+}
+
+impl ResourceGrid {
+    pub fn new(num_rb: u32, num_sym: u32) -> Self {
+        ResourceGrid { num_rb, num_sym, re: vec![Complex::ZERO; (num_rb * NUM_SC_PER_RB * num_sym) as usize] }
+    }
+
+    pub fn num_rb(&self) -> u32 {
+        self.num_rb
+    }
+
+    pub fn num_sym(&self) -> u32 {
+        self.num_sym
+    }
+
+    pub fn num_sc(&self) -> u32 {
+        self.num_rb * NUM_SC_PER_RB
+    }
+
+    pub fn set(&mut self, k: u32, l: u32, val: Complex) {
+        let index = self.re_index(k, l);
+        self.re[index] = val;
+    }
+
+    pub fn symbol(&self, l: u32) -> &[Complex] {
+        assert!(l < self.num_sym, "invalid symbol {}!", l);
+        let num_sc = self.num_sc() as usize;
+        &self.re[l as usize * num_sc..(l as usize + 1) * num_sc]
+    }
+
+    pub fn symbol_mut(&mut self, l: u32) -> &mut [Complex] {
+        assert!(l < self.num_sym, "invalid symbol {}!", l);
+        let num_sc = self.num_sc() as usize;
+        &mut self.re[l as usize * num_sc..(l as usize + 1) * num_sc]
+    }
+
+    pub fn clear(&mut self) {
+        self.re.fill(Complex::ZERO);
+    }
+
+    // true if any RE of the PRB in the symbol is not zero
+    pub fn is_prb_used(&self, prb: u32, l: u32) -> bool {
+        let start_sc = (prb * NUM_SC_PER_RB) as usize;
+        self.symbol(l)[start_sc..start_sc + NUM_SC_PER_RB as usize].iter().any(|&re| re != Complex::ZERO)
+    }
+
+    // one line per RE: "l k re im"
+    pub fn dump(&self, file_name: &str) -> Result<(), Error> {
+        let mut f = BufWriter::new(File::create(file_name)?);
+        for l in 0..self.num_sym {
+            for (k, re) in self.symbol(l).iter().enumerate() {
+                writeln!(f, "{} {} {} {}", l, k, re.re, re.im)?;
+            }
+        }
+        f.flush()?;
+        Ok(())
+    }
+
+    fn re_index(&self, k: u32, l: u32) -> usize {
+        assert!(k < self.num_sc() && l < self.num_sym, "invalid RE ({}, {})!", k, l);
+        (l * self.num_sc() + k) as usize
+    }
+}
+
+// occupancy map, one row per PRB and one column per symbol
+impl fmt::Display for ResourceGrid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "prb\\sym ")?;
+        for l in 0..self.num_sym {
+            write!(f, "{:>3}", l)?;
+        }
+        writeln!(f)?;
+        for prb in (0..self.num_rb).rev() {
+            write!(f, "{:>7} ", prb)?;
+            for l in 0..self.num_sym {
+                write!(f, "{:>3}", if self.is_prb_used(prb, l) { "x" } else { "." })?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// 38.211, 6.3.2.3.2 .. 6.3.2.6.5, mapping to physical resources, slot_idx is the slot index in a multi-slot PUCCH
+// the symbols from the generator are re-placed at the hop of the slot, so the intra and inter slot hopping are both kept
+pub fn map_pucch(
+    grid: &mut ResourceGrid,
+    pucch_config: &PucchConfig,
+    pucch_resource: &PucchResource,
+    slot_idx: u32,
+    symbols: &[PucchSymbol],
+) {
+    let (start_sym, _) = pucch_resource.occupied_sym();
+    for symbol in symbols {
+        let start_prb = pucch_resource.sym_start_prb(&pucch_config.pucch_formats, symbol.sym - start_sym, slot_idx);
+        let start_sc = start_prb * NUM_SC_PER_RB;
+        assert!(
+            start_sc as usize + symbol.data.len() <= grid.num_sc() as usize,
+            "PUCCH resource exceeds the grid at PRB {}!",
+            start_prb
+        );
+        let grid_sym = grid.symbol_mut(symbol.sym);
+        grid_sym[start_sc as usize..start_sc as usize + symbol.data.len()].copy_from_slice(&symbol.data);
+    }
+}
+
+// the PUCCH symbols of the resource in the grid, the inverse of map_pucch
+pub fn extract_pucch(
+    grid: &ResourceGrid,
+    pucch_config: &PucchConfig,
+    pucch_resource: &PucchResource,
+    slot_idx: u32,
+) -> Vec<PucchSymbol> {
+    let (start_sym, num_sym) = pucch_resource.occupied_sym();
+    let num_sc = (pucch_resource.num_rb() * NUM_SC_PER_RB) as usize;
+    (0..num_sym)
+        .map(|l| {
+            let start_prb = pucch_resource.sym_start_prb(&pucch_config.pucch_formats, l, slot_idx);
+            let start_sc = (start_prb * NUM_SC_PER_RB) as usize;
+            let data = grid.symbol(start_sym + l)[start_sc..start_sc + num_sc].to_vec();
+            PucchSymbol { sym: start_sym + l, start_prb, data }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pucch_tx::*;
+    use crate::test_util::*;
+
+    #[test]
+    fn map_with_hopping() {
+        let mut rrc_pucch_config = test_pucch_config_rrc(&[]);
+        let pucch_config = test_pucch_config(&[]);
+        let params = test_params(3);
+        let num_sym_per_slot = pucch_config.bwp.numerology.num_sym_per_slot();
+        let mut grid = ResourceGrid::new(pucch_config.bwp.num_rb, num_sym_per_slot);
+
+        // format 3, 6 symbols from symbol 6, intra slot hopping from PRB 10 to PRB 40
+        let resource = PucchResourceId::new(&pucch_config, 3).pucch_resource(&pucch_config);
+        let bits = (0..resource.e_tot(&pucch_config.pucch_formats)).map(|i| (i % 2) as u8).collect::<Vec<_>>();
+        let symbols = pucch_format34_gen(&pucch_config, resource, &params, &bits);
+        map_pucch(&mut grid, &pucch_config, resource, 0, &symbols);
+        assert!((6..9).all(|l| grid.is_prb_used(10, l) && grid.is_prb_used(19, l) && !grid.is_prb_used(40, l)));
+        assert!((9..12).all(|l| grid.is_prb_used(40, l) && grid.is_prb_used(49, l) && !grid.is_prb_used(10, l)));
+        assert!(!grid.is_prb_used(20, 6) && !grid.is_prb_used(50, 9) && !grid.is_prb_used(10, 12));
+
+        // inter slot hopping, the whole slot 1 on the second hop
+        rrc_pucch_config.pucch_format3.inter_slot_freq_hopping = true;
+        rrc_pucch_config.pucch_format3.num_slots = Some(2);
+        let pucch_config: PucchConfig = rrc_pucch_config.into();
+        let resource = PucchResourceId::new(&pucch_config, 3).pucch_resource(&pucch_config);
+        let symbols = pucch_format34_gen(&pucch_config, resource, &params, &bits);
+        for (slot_idx, prb) in [(0, 10), (1, 40)] {
+            grid.clear();
+            map_pucch(&mut grid, &pucch_config, resource, slot_idx, &symbols);
+            assert!((0..num_sym_per_slot).all(|l| grid.is_prb_used(prb, l) == (6..12).contains(&l)));
+        }
+    }
+}
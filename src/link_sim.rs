@@ -0,0 +1,330 @@
+use crate::channel::*;
+use crate::combining::Combining;
+use crate::complex::*;
+use crate::err::Error;
+use crate::polar::*;
+use crate::pucch::*;
+use crate::pucch_rx::*;
+use crate::pucch_tx::*;
+use crate::read_config::load_config;
+use crate::reed_muller::*;
+use crate::rrc_pucch::*;
+use serde_derive::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+const POLAR_LIST_SIZE: usize = 8;
+
+// one SNR sweep of a PUCCH resource, every trial is a new channel realization
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkSimConfig {
+    pub pucch_resource_id: u32,
+    pub o_harq: u32,            // HARQ-ACK bits, the first UCI bits of format 2/3/4
+    pub o_csi: u32,             // CSI bits after the HARQ-ACK, format 2/3/4 only
+    pub channel: ChannelConfig, // snr_db is overridden by the sweep
+    pub snr_db: Vec<f32>,
+    pub num_trials: u32, // per SNR for each of the ACK, NACK and DTX transmissions
+    pub combining: Combining,
+    pub dtx_threshold: f32, // format 0/1 and Reed-Muller DTX detection, polar coded UCI relies on the CRC
+    pub seed: u64,
+}
+
+// HARQ-ACK rates are per bit, false alarm and BLER are per transmission
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct LinkSimPoint {
+    pub snr_db: f32,
+    pub num_trials: u32,
+    pub ack_miss: f32,    // ACK decoded as NACK or DTX
+    pub nack_to_ack: f32, // NACK decoded as ACK, over the NACK bits not detected as DTX
+    pub dtx_to_ack: f32,  // ACK decoded when nothing is transmitted
+    pub false_alarm: f32, // PUCCH detected when nothing is transmitted
+    pub bler: f32,        // UCI of the ACK and NACK transmissions not decoded correctly, including DTX
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Transmission {
+    Ack,
+    Nack,
+    Dtx,
+}
+
+enum UciCode {
+    None, // format 0/1, the HARQ-ACK bits are not channel coded
+    ReedMuller(ReedMuller),
+    Polar(UciPolar),
+}
+
+// HARQ-ACK and UCI block errors of the trials of one SNR
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ErrorCount {
+    o_harq: usize,
+    num_trials: usize, // of each transmission
+    ack_miss: usize,
+    nack_to_ack: usize,
+    nack_decoded: usize, // HARQ-ACK bits of the NACK transmissions not detected as DTX
+    dtx_to_ack: usize,
+    false_alarm: usize,
+    block_error: usize,
+}
+
+impl ErrorCount {
+    pub(crate) fn new(o_harq: u32, num_trials: u32) -> Self {
+        ErrorCount { o_harq: o_harq as usize, num_trials: num_trials as usize, ..Default::default() }
+    }
+
+    // uci is the transmitted UCI, not used for DTX, decoded is None when DTX is detected
+    pub(crate) fn add(&mut self, transmission: Transmission, uci: &[u8], decoded: Option<&Vec<u8>>) {
+        let o_harq = self.o_harq;
+        let num_ack = decoded.map_or(0, |bits| bits[..o_harq].iter().filter(|&&bit| bit == 1).count());
+        match transmission {
+            Transmission::Ack => self.ack_miss += o_harq - num_ack,
+            Transmission::Nack => {
+                self.nack_to_ack += num_ack;
+                self.nack_decoded += o_harq * decoded.is_some() as usize;
+            }
+            Transmission::Dtx => {
+                self.dtx_to_ack += num_ack;
+                self.false_alarm += decoded.is_some() as usize;
+            }
+        }
+        if transmission != Transmission::Dtx && decoded.map(|bits| bits.as_slice()) != Some(uci) {
+            self.block_error += 1;
+        }
+    }
+
+    pub(crate) fn point(&self, snr_db: f32) -> LinkSimPoint {
+        let ratio = |num: usize, den: usize| if den > 0 { num as f32 / den as f32 } else { 0f32 };
+        let (num_trials, num_bits) = (self.num_trials, self.num_trials * self.o_harq);
+        LinkSimPoint {
+            snr_db,
+            num_trials: num_trials as u32,
+            ack_miss: ratio(self.ack_miss, num_bits),
+            nack_to_ack: ratio(self.nack_to_ack, self.nack_decoded),
+            dtx_to_ack: ratio(self.dtx_to_ack, num_bits),
+            false_alarm: ratio(self.false_alarm, num_trials),
+            bler: ratio(self.block_error, 2 * num_trials),
+        }
+    }
+}
+
+// generation, channel, reception and decoding of a PUCCH resource
+pub struct LinkSim<'a> {
+    pucch_config: &'a PucchConfig,
+    pucch_resource: &'a PucchResource,
+    params: PucchTxParams,
+    config: LinkSimConfig,
+    uci_code: UciCode,
+}
+
+impl<'a> LinkSim<'a> {
+    pub fn new(pucch_config: &'a PucchConfig, params: PucchTxParams, config: &LinkSimConfig) -> Self {
+        let pucch_resource = PucchResourceId::new(pucch_config, config.pucch_resource_id).pucch_resource(pucch_config);
+        let a = config.o_harq + config.o_csi;
+        let uci_code = match pucch_resource.format {
+            PucchFormat::Format0 { .. } | PucchFormat::Format1 { .. } => {
+                assert!(config.o_csi == 0 && (1..=2).contains(&config.o_harq), "invalid PUCCH format 0/1 UCI!");
+                UciCode::None
+            }
+            _ => {
+                let e_tot = pucch_resource.e_tot(&pucch_config.pucch_formats);
+                match a {
+                    0..=2 => panic!("UCI of {} bits on PUCCH format 2/3/4 is not supported!", a),
+                    3..=11 => UciCode::ReedMuller(ReedMuller::new(a, e_tot)),
+                    _ => UciCode::Polar(UciPolar::new(a, e_tot)),
+                }
+            }
+        };
+        LinkSim { pucch_config, pucch_resource, params, config: config.clone(), uci_code }
+    }
+
+    // HARQ-ACK bits of the transmission followed by random CSI bits
+    pub(crate) fn uci(&self, transmission: Transmission, rng: &mut Rng) -> Vec<u8> {
+        let harq_ack = vec![(transmission == Transmission::Ack) as u8; self.config.o_harq as usize];
+        let csi = (0..self.config.o_csi).map(|_| (rng.next_u64() & 1) as u8).collect::<Vec<_>>();
+        [harq_ack, csi].concat()
+    }
+
+    pub(crate) fn transmit(&self, params: &PucchTxParams, uci: &[u8]) -> Vec<PucchSymbol> {
+        let (pucch_config, pucch_resource) = (self.pucch_config, self.pucch_resource);
+        // zero padding up to E_tot when 2 polar code blocks leave a bit
+        let coded_bits = || {
+            let mut bits = match &self.uci_code {
+                UciCode::ReedMuller(code) => code.encode(uci),
+                UciCode::Polar(code) => code.encode(uci),
+                UciCode::None => panic!("impossible to be here!"),
+            };
+            bits.resize(pucch_resource.e_tot(&pucch_config.pucch_formats) as usize, 0);
+            bits
+        };
+        match pucch_resource.format {
+            PucchFormat::Format0 { .. } => pucch_format0_gen(pucch_config, pucch_resource, params, uci, false),
+            PucchFormat::Format1 { .. } => pucch_format1_gen(pucch_config, pucch_resource, params, uci),
+            PucchFormat::Format2 { .. } => pucch_format2_gen(pucch_config, pucch_resource, params, &coded_bits()),
+            PucchFormat::Format3 { .. } | PucchFormat::Format4 { .. } => {
+                pucch_format34_gen(pucch_config, pucch_resource, params, &coded_bits())
+            }
+        }
+    }
+
+    // the decoded UCI, None for DTX
+    pub(crate) fn receive(&self, params: &PucchTxParams, rx: &[Vec<PucchSymbol>]) -> Option<Vec<u8>> {
+        let (pucch_config, pucch_resource) = (self.pucch_config, self.pucch_resource);
+        let (o_harq, combining, dtx_threshold) = (self.config.o_harq, self.config.combining, self.config.dtx_threshold);
+        let llr = match pucch_resource.format {
+            PucchFormat::Format0 { .. } => {
+                let decoded = pucch_format0_rx(pucch_config, pucch_resource, params, rx, o_harq, false, dtx_threshold);
+                return (!decoded.dtx).then_some(decoded.harq_ack);
+            }
+            PucchFormat::Format1 { .. } => {
+                let decoded =
+                    pucch_format1_rx(pucch_config, pucch_resource, params, rx, o_harq as usize, combining, dtx_threshold);
+                return (!decoded.dtx).then(|| decoded.llr.iter().map(|&llr| (llr < 0f32) as u8).collect());
+            }
+            PucchFormat::Format2 { .. } => pucch_format2_rx(pucch_config, pucch_resource, params, rx, combining).llr,
+            PucchFormat::Format3 { .. } | PucchFormat::Format4 { .. } => {
+                pucch_format34_rx(pucch_config, pucch_resource, params, rx, combining).llr
+            }
+        };
+        match &self.uci_code {
+            UciCode::ReedMuller(code) => {
+                let decoded = code.decode(&llr, dtx_threshold);
+                (!decoded.dtx).then_some(decoded.bits)
+            }
+            UciCode::Polar(code) => {
+                let decoded = code.decode(&llr, POLAR_LIST_SIZE);
+                decoded.crc_pass.then_some(decoded.bits)
+            }
+            UciCode::None => panic!("impossible to be here!"),
+        }
+    }
+
+    fn run_snr(&self, snr_db: f32, rng: &mut Rng) -> LinkSimPoint {
+        let num_trials = self.config.num_trials;
+        let channel_config = ChannelConfig { snr_db, ..self.config.channel };
+        let numerology = self.pucch_config.bwp.numerology;
+        let mut count = ErrorCount::new(self.config.o_harq, num_trials);
+
+        for transmission in [Transmission::Ack, Transmission::Nack, Transmission::Dtx] {
+            for trial in 0..num_trials {
+                let params = PucchTxParams { slot: trial % numerology.num_slot_per_frame(), ..self.params };
+                let uci = self.uci(transmission, rng);
+
+                let mut tx = self.transmit(&params, &uci);
+                if transmission == Transmission::Dtx {
+                    tx.iter_mut().flat_map(|symbol| symbol.data.iter_mut()).for_each(|x| *x = Complex::ZERO);
+                }
+                let rx = Channel::new(&channel_config, numerology, rng.next_u64()).apply(&tx, 0);
+                let decoded = self.receive(&params, &rx);
+                count.add(transmission, &uci, decoded.as_ref());
+            }
+        }
+        count.point(snr_db)
+    }
+
+    pub fn run(&self) -> Vec<LinkSimPoint> {
+        let mut rng = Rng::new(self.config.seed);
+        self.config.snr_db.iter().map(|&snr_db| self.run_snr(snr_db, &mut rng)).collect()
+    }
+}
+
+pub fn write_link_sim_csv(file_name: &str, points: &[LinkSimPoint]) -> Result<(), Error> {
+    let mut f = BufWriter::new(File::create(file_name)?);
+    writeln!(f, "snr_db,num_trials,ack_miss,nack_to_ack,dtx_to_ack,false_alarm,bler")?;
+    for p in points {
+        writeln!(
+            f,
+            "{},{},{:e},{:e},{:e},{:e},{:e}",
+            p.snr_db, p.num_trials, p.ack_miss, p.nack_to_ack, p.dtx_to_ack, p.false_alarm, p.bler
+        )?;
+    }
+    Ok(())
+}
+
+pub fn write_link_sim_json(file_name: &str, points: &[LinkSimPoint]) -> Result<(), Error> {
+    let f = BufWriter::new(File::create(file_name)?);
+    serde_json::to_writer_pretty(f, points).map_err(|_| Error::Nr("cannot serialize link simulation results!"))?;
+    Ok(())
+}
+
+// sweep of link_sim_config_file on the PUCCH-Config of pucch_config_file, written to <output_file_stem>.csv and .json
+pub fn run_link_sim(
+    pucch_config_file: &str,
+    link_sim_config_file: &str,
+    params: PucchTxParams,
+    output_file_stem: &str,
+) -> Result<Vec<LinkSimPoint>, Error> {
+    let pucch_config: PucchConfig = load_config::<PucchConfigR>(pucch_config_file)?.into();
+    let link_sim_config = load_config::<LinkSimConfig>(link_sim_config_file)?;
+    let points = LinkSim::new(&pucch_config, params, &link_sim_config).run();
+    write_link_sim_csv(&format!("{}.csv", output_file_stem), &points)?;
+    write_link_sim_json(&format!("{}.json", output_file_stem), &points)?;
+    Ok(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    fn sim_config(pucch_resource_id: u32, o_harq: u32, o_csi: u32, snr_db: Vec<f32>) -> LinkSimConfig {
+        LinkSimConfig {
+            pucch_resource_id,
+            o_harq,
+            o_csi,
+            channel: ChannelConfig::default(),
+            snr_db,
+            num_trials: 20,
+            combining: Combining::Mrc,
+            dtx_threshold: 0.5,
+            seed: 1,
+        }
+    }
+
+    #[test]
+    fn nack_to_ack_over_decoded() {
+        // the DTX detected NACK is a block error but not a NACK to ACK error
+        let mut count = ErrorCount::new(2, 2);
+        count.add(Transmission::Nack, &[0, 0], None);
+        count.add(Transmission::Nack, &[0, 0], Some(&vec![1, 0]));
+        let point = count.point(0f32);
+        assert_eq!((point.nack_to_ack, point.bler), (0.5, 0.5));
+    }
+
+    #[test]
+    fn link_sim_sweep() {
+        let pucch_config = test_pucch_config(&[PucchResourceR {
+            pucch_resource_id: 10,
+            start_prb: 8,
+            intra_slot_freq_hopping: IntraSlotFreqHopping::Hopping { second_prb: 30 },
+            format: PucchFormat::Format1 { init_cyclic_shift: 0, num_sym: 14, start_sym: 0, time_occ: 1 },
+        }]);
+        let params = test_params(0);
+        // format 1 with 2 HARQ-ACK bits and format 2 with Reed-Muller coded UCI
+        for config in [sim_config(10, 2, 0, vec![-20f32, 20f32]), sim_config(2, 2, 4, vec![-20f32, 20f32])] {
+            let points = LinkSim::new(&pucch_config, params, &config).run();
+            let (low, high) = (points[0], points[1]);
+            assert!(low.ack_miss > 0.5 && low.bler > 0.5, "{:?}", low);
+            assert_eq!((high.ack_miss, high.nack_to_ack, high.dtx_to_ack, high.bler), (0f32, 0f32, 0f32, 0f32));
+            assert_eq!(high.false_alarm, 0f32);
+        }
+        let points = LinkSim::new(&pucch_config, params, &sim_config(3, 4, 16, vec![10f32])).run();
+        assert_eq!((points[0].bler, points[0].false_alarm), (0f32, 0f32));
+
+        let file_name = "target/link_sim_test.csv";
+        write_link_sim_csv(file_name, &points).unwrap();
+        assert_eq!(std::fs::read_to_string(file_name).unwrap().lines().count(), 2);
+        let file_name = "target/link_sim_test.json";
+        write_link_sim_json(file_name, &points).unwrap();
+        assert_eq!(load_config::<Vec<LinkSimPoint>>(file_name).unwrap(), points);
+    }
+
+    #[test]
+    fn link_sim_files() {
+        let config_file = "target/link_sim_test_config.json";
+        std::fs::write(config_file, serde_json::to_string(&sim_config(2, 2, 4, vec![0f32, 20f32])).unwrap()).unwrap();
+        let points = run_link_sim(TEST_PUCCH_CONFIG_FILE, config_file, test_params(0), "target/link_sim_test_run").unwrap();
+        assert_eq!(points.len(), 2);
+        assert_eq!(std::fs::read_to_string("target/link_sim_test_run.csv").unwrap().lines().count(), 3);
+        assert_eq!(load_config::<Vec<LinkSimPoint>>("target/link_sim_test_run.json").unwrap(), points);
+    }
+}
@@ -1,18 +1,111 @@
 #[macro_use]
 mod macros;
+mod bwp;
+mod channel;
+mod combining;
+mod complex;
+mod conformance;
 mod constants;
+mod crc;
+mod dft;
 mod err;
+mod grid;
+mod gold;
+mod link_sim;
 mod math;
+mod modulation;
+mod multi_ue;
+mod numerology;
+mod occupancy;
+mod ofdm;
 mod optional;
+mod polar;
 mod pucch;
+mod pucch_rx;
+mod pucch_tx;
 mod read_config;
+mod reed_muller;
 mod rrc_pucch;
+mod sequence;
+mod sigmf;
+#[cfg(test)]
+mod test_util;
+mod waveform;
+use combining::Combining;
+use conformance::run_conformance;
+use err::Error;
+use link_sim::{run_link_sim, LinkSimConfig};
+use multi_ue::run_multi_ue;
 use pucch::*;
+use pucch_tx::PucchTxParams;
 use rrc_pucch::PucchConfigR;
+use waveform::{export_sigmf, import_sigmf};
 
 use crate::read_config::load_config;
 
 fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let params = PucchTxParams { slot: 0, cell_id: 1, n_rnti: 0x4601, data_scrambling_id: None, dmrs_scrambling_id: None };
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        // nr link_sim <LinkSimConfig json file> <output file stem>
+        ["link_sim", link_sim_config_file, output_file_stem] => {
+            match run_link_sim("input/pucch_config.json", link_sim_config_file, params, output_file_stem) {
+                Ok(points) => points.iter().for_each(|point| println!("{:?}", point)),
+                Err(e) => {
+                    eprintln!("{:?}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        // nr conformance <number of trials per transmission>
+        ["conformance", num_trials] => {
+            let Ok(num_trials) = num_trials.parse::<u32>() else {
+                eprintln!("invalid number of trials {}!", num_trials);
+                std::process::exit(1);
+            };
+            match run_conformance("input/pucch_config.json", params, Combining::Mrc, num_trials, 1) {
+                Ok(results) if results.iter().all(|result| result.pass()) => (),
+                Ok(_) => std::process::exit(1),
+                Err(e) => {
+                    eprintln!("{:?}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        // nr multi_ue <MultiUeConfig json file>
+        ["multi_ue", multi_ue_config_file] => {
+            exit_on_error(run_multi_ue("input/pucch_config.json", multi_ue_config_file, params));
+            return;
+        }
+        // nr sigmf_tx <LinkSimConfig json file> <SigMF base name> <number of slots> <point A frequency in Hz>
+        ["sigmf_tx", link_sim_config_file, base_name, num_slots, point_a_hz] => {
+            let (Ok(num_slots), Ok(point_a_hz)) = (num_slots.parse::<u32>(), point_a_hz.parse::<f64>()) else {
+                eprintln!("invalid number of slots {} or point A frequency {}!", num_slots, point_a_hz);
+                std::process::exit(1);
+            };
+            let (pucch_config, config) = exit_on_error(load_waveform_config(link_sim_config_file));
+            let meta = exit_on_error(export_sigmf(&pucch_config, params, &config, num_slots, point_a_hz, base_name));
+            meta.annotations.iter().for_each(|annotation| println!("{:?}", annotation));
+            return;
+        }
+        // nr sigmf_rx <LinkSimConfig json file> <SigMF base name>
+        ["sigmf_rx", link_sim_config_file, base_name] => {
+            let (pucch_config, config) = exit_on_error(load_waveform_config(link_sim_config_file));
+            let decoded = exit_on_error(import_sigmf(&pucch_config, params, &config, base_name));
+            for transmission in &decoded {
+                let annotation = &transmission.annotation;
+                println!("{:?}: UCI {:?}, decoded {:?}", annotation.label, annotation.uci, transmission.decoded);
+            }
+            if !decoded.iter().all(|transmission| transmission.is_correct()) {
+                std::process::exit(1);
+            }
+            return;
+        }
+        _ => (),
+    }
+
     println!("Hello, world!");
 
     // let pucch_config = PucchConfigR::default();
@@ -31,7 +124,7 @@ fn main() {
         }
         Err(e) => {
             println!("{:?}", e);
-            return ();
+            return;
         }
     };
 
@@ -58,3 +151,16 @@ fn main() {
 
     pucch_proc(&pucch_config, &mut channels);
 }
+
+fn exit_on_error<T>(result: Result<T, Error>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("{:?}", e);
+        std::process::exit(1);
+    })
+}
+
+// the cell PUCCH-Config and the resource and UCI of the recording
+fn load_waveform_config(link_sim_config_file: &str) -> Result<(PucchConfig, LinkSimConfig), Error> {
+    let pucch_config: PucchConfig = load_config::<PucchConfigR>("input/pucch_config.json")?.into();
+    Ok((pucch_config, load_config::<LinkSimConfig>(link_sim_config_file)?))
+}
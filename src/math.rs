@@ -3,7 +3,7 @@ pub fn floor(x: u32, y: u32) -> u32 {
 }
 
 pub fn ceil(x: u32, y: u32) -> u32 {
-    (x + y - 1) / y
+    x.div_ceil(y)
 }
 
 pub fn mask(bit_offset: u32, bit_len: u32) -> u32 {
@@ -29,7 +29,7 @@ pub fn swap_remove_multiple<T>(vector: &mut Vec<T>, mut idx_to_remove: Vec<usize
     }
 }
 
-pub fn filter_index<T, F>(vector: &mut Vec<T>, mut predicate: F) -> Vec<usize>
+pub fn filter_index<T, F>(vector: &mut [T], mut predicate: F) -> Vec<usize>
 where
     F: FnMut(&T) -> bool,
 {
@@ -43,3 +43,8 @@ where
     let index = vector.iter().enumerate().filter(|&(_i, item)| predicate(item)).map(|(i, _item)| i).collect::<Vec<_>>();
     swap_remove_multiple(vector, index);
 }
+
+// ceil(log2(x)), x > 0
+pub fn ceil_log2(x: u32) -> u32 {
+    32 - (x - 1).leading_zeros()
+}
@@ -0,0 +1,43 @@
+use crate::complex::*;
+use std::f32::consts::{FRAC_1_SQRT_2, SQRT_2};
+
+// 38.211, 5.1.2, BPSK
+pub fn bpsk(bit: u8) -> Complex {
+    let a = FRAC_1_SQRT_2 * (1f32 - 2f32 * bit as f32);
+    Complex::new(a, a)
+}
+
+// 38.211, 5.1.3, QPSK
+pub fn qpsk(bit0: u8, bit1: u8) -> Complex {
+    Complex::new(FRAC_1_SQRT_2 * (1f32 - 2f32 * bit0 as f32), FRAC_1_SQRT_2 * (1f32 - 2f32 * bit1 as f32))
+}
+
+pub fn qpsk_modulate(bits: &[u8]) -> Vec<Complex> {
+    assert!(bits.len().is_multiple_of(2), "QPSK expects even number of bits, {}!", bits.len());
+    bits.chunks(2).map(|pair| qpsk(pair[0], pair[1])).collect()
+}
+
+// 38.211, 5.1.1, pi/2-BPSK
+pub fn pi2_bpsk_modulate(bits: &[u8]) -> Vec<Complex> {
+    bits.iter().enumerate().map(|(i, &bit)| if i % 2 == 0 { bpsk(bit) } else { Complex::I * bpsk(bit) }).collect()
+}
+
+// LLR of QPSK, positive for bit 0, noise_var is the noise variance of each symbol
+pub fn qpsk_demodulate(x: &[Complex], noise_var: &[f32]) -> Vec<f32> {
+    x.iter()
+        .zip(noise_var)
+        .flat_map(|(x, &noise_var)| [2f32 * SQRT_2 * x.re / noise_var, 2f32 * SQRT_2 * x.im / noise_var])
+        .collect()
+}
+
+// LLR of pi/2-BPSK, positive for bit 0
+pub fn pi2_bpsk_demodulate(x: &[Complex], noise_var: &[f32]) -> Vec<f32> {
+    x.iter()
+        .zip(noise_var)
+        .enumerate()
+        .map(|(i, (&x, &noise_var))| {
+            let x = if i % 2 == 0 { x } else { x * Complex::I.conj() };
+            2f32 * SQRT_2 * (x.re + x.im) / noise_var
+        })
+        .collect()
+}
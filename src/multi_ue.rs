@@ -0,0 +1,279 @@
+use crate::channel::*;
+use crate::combining::Combining;
+use crate::complex::*;
+use crate::constants::*;
+use crate::err::Error;
+use crate::grid::*;
+use crate::link_sim::*;
+use crate::occupancy::*;
+use crate::pucch::*;
+use crate::pucch_tx::*;
+use crate::read_config::load_config;
+use crate::rrc_pucch::*;
+use serde_derive::{Deserialize, Serialize};
+
+// one UE of the code-domain multiplexing, each UE has its own fading, SNR and timing offset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UeConfig {
+    pub pucch_resource_id: u32,
+    pub n_rnti: u32,
+    pub o_harq: u32,
+    pub o_csi: u32,             // format 2/3/4 only
+    pub channel: ChannelConfig, // snr_db is the received SNR of the UE, the noise is common to all the UEs
+}
+
+// UEs of one cell on shared PUCCH PRBs, separated by the cyclic shifts and the OCC of format 0/1/4
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiUeConfig {
+    pub ues: Vec<UeConfig>,
+    pub num_trials: u32, // per UE for each of the ACK, NACK and DTX transmissions
+    pub combining: Combining,
+    pub dtx_threshold: f32,
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MultiUeResult {
+    pub point: LinkSimPoint, // detection and decoding of the UE with the other UEs transmitting
+    pub isr_db: f32,         // interference of the other UEs over the own signal after despreading with the own code
+}
+
+// the transmissions rotate over the UEs, so each trial mixes active and DTX UEs
+const TRANSMISSIONS: [Transmission; 3] = [Transmission::Ack, Transmission::Nack, Transmission::Dtx];
+
+pub struct MultiUeSim<'a> {
+    pucch_config: &'a PucchConfig,
+    params: PucchTxParams,
+    config: MultiUeConfig,
+    link_sims: Vec<LinkSim<'a>>,
+}
+
+impl<'a> MultiUeSim<'a> {
+    // params is the cell, n_rnti is replaced by the one of each UE
+    pub fn new(pucch_config: &'a PucchConfig, params: PucchTxParams, config: &MultiUeConfig) -> Self {
+        assert!(!config.ues.is_empty(), "invalid multi-UE config without UE!");
+        let num_rx = config.ues[0].channel.num_rx;
+        assert!(config.ues.iter().all(|ue| ue.channel.num_rx == num_rx), "invalid receive antennas of the UEs!");
+        let link_sims = config
+            .ues
+            .iter()
+            .map(|ue| {
+                let link_sim_config = LinkSimConfig {
+                    pucch_resource_id: ue.pucch_resource_id,
+                    o_harq: ue.o_harq,
+                    o_csi: ue.o_csi,
+                    channel: ue.channel,
+                    snr_db: vec![ue.channel.snr_db],
+                    num_trials: config.num_trials,
+                    combining: config.combining,
+                    dtx_threshold: config.dtx_threshold,
+                    seed: config.seed,
+                };
+                LinkSim::new(pucch_config, PucchTxParams { n_rnti: ue.n_rnti, ..params }, &link_sim_config)
+            })
+            .collect();
+        MultiUeSim { pucch_config, params, config: config.clone(), link_sims }
+    }
+
+    fn pucch_resource(&self, ue: usize) -> &'a PucchResource {
+        PucchResourceId::new(self.pucch_config, self.config.ues[ue].pucch_resource_id).pucch_resource(self.pucch_config)
+    }
+
+    // pairs of UEs that are not orthogonal in the allocation, the simulation still runs them to show the interference
+    pub fn collisions(&self) -> Vec<(usize, usize)> {
+        let occupancy = (0..self.config.ues.len())
+            .map(|ue| PucchOccupancy::new(self.pucch_config, self.pucch_resource(ue), 0, self.config.ues[ue].o_harq, false))
+            .collect::<Vec<_>>();
+        let hopping = &self.pucch_config.pucch_group_seq_hopping;
+        (0..occupancy.len())
+            .flat_map(|a| (a + 1..occupancy.len()).map(move |b| (a, b)))
+            .filter(|&(a, b)| occupancy[a].is_collision(&occupancy[b], hopping))
+            .collect()
+    }
+
+    // one result per UE
+    pub fn run(&self) -> Vec<MultiUeResult> {
+        let pucch_config = self.pucch_config;
+        let numerology = pucch_config.bwp.numerology;
+        let num_ue = self.config.ues.len();
+        let num_rx = self.config.ues[0].channel.num_rx as usize;
+        let mut rng = Rng::new(self.config.seed);
+        let mut counts =
+            self.config.ues.iter().map(|ue| ErrorCount::new(ue.o_harq, self.config.num_trials)).collect::<Vec<_>>();
+        let mut leakage = vec![(0f32, 0f32); num_ue];
+        let mut grids = vec![ResourceGrid::new(pucch_config.bwp.num_rb, numerology.num_sym_per_slot()); num_rx];
+
+        for trial in 0..TRANSMISSIONS.len() as u32 * self.config.num_trials {
+            let slot = trial % numerology.num_slot_per_frame();
+            grids.iter_mut().for_each(|grid| grid.clear());
+            let mut ucis = Vec::with_capacity(num_ue);
+            let mut signals = Vec::with_capacity(num_ue);
+            for (ue, (config, link_sim)) in self.config.ues.iter().zip(&self.link_sims).enumerate() {
+                let transmission = TRANSMISSIONS[(trial as usize + ue) % TRANSMISSIONS.len()];
+                let uci = link_sim.uci(transmission, &mut rng);
+                let channel = Channel::new(&config.channel, numerology, rng.next_u64());
+                let signal = (transmission != Transmission::Dtx).then(|| {
+                    let params = PucchTxParams { slot, n_rnti: config.n_rnti, ..self.params };
+                    let amplitude = 10f32.powf(config.channel.snr_db / 20f32);
+                    let mut faded = channel.fade(&link_sim.transmit(&params, &uci), 0);
+                    faded.iter_mut().flatten().flat_map(|symbol| symbol.data.iter_mut()).for_each(|x| *x *= amplitude);
+                    for (grid, faded) in grids.iter_mut().zip(&faded) {
+                        for symbol in faded {
+                            let start_sc = (symbol.start_prb * NUM_SC_PER_RB) as usize;
+                            let grid_sym = &mut grid.symbol_mut(symbol.sym)[start_sc..start_sc + symbol.data.len()];
+                            grid_sym.iter_mut().zip(&symbol.data).for_each(|(re, &x)| *re += x);
+                        }
+                    }
+                    faded
+                });
+                ucis.push((transmission, uci));
+                signals.push(signal);
+            }
+
+            for (leakage, signal) in leakage.iter_mut().zip(&signals) {
+                if let Some(signal) = signal {
+                    let (interference, power) = code_leakage(&grids, signal);
+                    *leakage = (leakage.0 + interference, leakage.1 + power);
+                }
+            }
+
+            // unit thermal noise, the SNR of each UE is in its amplitude
+            for grid in grids.iter_mut() {
+                for l in 0..grid.num_sym() {
+                    grid.symbol_mut(l).iter_mut().for_each(|re| *re += rng.complex_gaussian(1f32));
+                }
+            }
+
+            for (ue, (transmission, uci)) in ucis.iter().enumerate() {
+                let params = PucchTxParams { slot, n_rnti: self.config.ues[ue].n_rnti, ..self.params };
+                let rx = grids
+                    .iter()
+                    .map(|grid| extract_pucch(grid, pucch_config, self.pucch_resource(ue), 0))
+                    .collect::<Vec<_>>();
+                let decoded = self.link_sims[ue].receive(&params, &rx);
+                counts[ue].add(*transmission, uci, decoded.as_ref());
+            }
+        }
+
+        (0..num_ue)
+            .map(|ue| {
+                let (interference, power) = leakage[ue];
+                MultiUeResult {
+                    point: counts[ue].point(self.config.ues[ue].channel.snr_db),
+                    isr_db: 10f32 * (interference / power.max(f32::MIN_POSITIVE)).max(f32::MIN_POSITIVE).log10(),
+                }
+            })
+            .collect()
+    }
+}
+
+// (interference, signal) power of a UE after the ideal despreading with its own faded signal, coherent over the symbols
+// of each hop, grids are the noise-free sum of all the UEs, signal is indexed by [antenna][symbol]
+// the receiver noise estimates also hold the orthogonal UEs, so the leakage is from the known signals instead
+fn code_leakage(grids: &[ResourceGrid], signal: &[Vec<PucchSymbol>]) -> (f32, f32) {
+    let (mut interference, mut power) = (0f32, 0f32);
+    for (grid, signal) in grids.iter().zip(signal) {
+        // (start PRB, correlation, energy) of each hop
+        let mut hops: Vec<(u32, Complex, f32)> = Vec::new();
+        for symbol in signal {
+            let start_sc = (symbol.start_prb * NUM_SC_PER_RB) as usize;
+            let grid_sym = &grid.symbol(symbol.sym)[start_sc..start_sc + symbol.data.len()];
+            let corr = grid_sym.iter().zip(&symbol.data).map(|(&re, &s)| (re - s) * s.conj()).sum::<Complex>();
+            let energy = symbol.data.iter().map(|s| s.norm_sqr()).sum::<f32>();
+            match hops.iter_mut().find(|hop| hop.0 == symbol.start_prb) {
+                Some(hop) => (hop.1, hop.2) = (hop.1 + corr, hop.2 + energy),
+                None => hops.push((symbol.start_prb, corr, energy)),
+            }
+        }
+        interference += hops.iter().map(|hop| hop.1.norm_sqr()).sum::<f32>();
+        power += hops.iter().map(|hop| hop.2 * hop.2).sum::<f32>();
+    }
+    (interference, power)
+}
+
+// multi_ue_config_file on the PUCCH-Config of pucch_config_file, the colliding UE pairs and the result of each UE are
+// printed
+pub fn run_multi_ue(
+    pucch_config_file: &str,
+    multi_ue_config_file: &str,
+    params: PucchTxParams,
+) -> Result<Vec<MultiUeResult>, Error> {
+    let pucch_config: PucchConfig = load_config::<PucchConfigR>(pucch_config_file)?.into();
+    let multi_ue_config = load_config::<MultiUeConfig>(multi_ue_config_file)?;
+    let sim = MultiUeSim::new(&pucch_config, params, &multi_ue_config);
+    for (a, b) in sim.collisions() {
+        println!("UE {} and UE {} collide", a, b);
+    }
+    let results = sim.run();
+    for (ue, result) in results.iter().enumerate() {
+        println!("UE {}: {:?}", ue, result);
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    fn ue(pucch_resource_id: u32, n_rnti: u32, o_harq: u32, o_csi: u32, timing_offset_ns: f32) -> UeConfig {
+        let channel = ChannelConfig { num_rx: 2, snr_db: 10f32, timing_offset_ns, ..Default::default() };
+        UeConfig { pucch_resource_id, n_rnti, o_harq, o_csi, channel }
+    }
+
+    #[test]
+    fn multiplexing() {
+        let mut resources = Vec::new();
+        let hopping = IntraSlotFreqHopping::Hopping { second_prb: 40 };
+        let mut push = |pucch_resource_id, format| {
+            resources.push(PucchResourceR { pucch_resource_id, start_prb: 5, intra_slot_freq_hopping: hopping, format })
+        };
+        // format 0 with the initial cyclic shifts 0 / 4 / 8
+        for (id, init_cyclic_shift) in [(20, 0), (21, 4), (22, 8)] {
+            push(id, PucchFormat::Format0 { init_cyclic_shift, num_sym: 2, start_sym: 12 });
+        }
+        // format 1 with the cyclic shifts 0 / 6 and the time domain OCC 0 / 1
+        for (id, init_cyclic_shift, time_occ) in [(30, 0, 0), (31, 0, 1), (32, 6, 0)] {
+            push(id, PucchFormat::Format1 { init_cyclic_shift, num_sym: 12, start_sym: 0, time_occ });
+        }
+        // format 4 with the pre-DFT OCC 0 / 1
+        for (id, occ_idx) in [(40, 0), (41, 1)] {
+            push(id, PucchFormat::Format4 { num_sym: 12, occ_len: 2, occ_idx, start_sym: 0 });
+        }
+        let mut pucch_config_rrc = test_pucch_config_rrc(&resources);
+        pucch_config_rrc.pucch_format4.pi2_bpsk = false;
+        let pucch_config: PucchConfig = pucch_config_rrc.into();
+        let params = test_params(0);
+        let config = |ues| MultiUeConfig { ues, num_trials: 10, combining: Combining::Mrc, dtx_threshold: 0.4, seed: 1 };
+
+        for ues in [
+            vec![ue(20, 1, 1, 0, 0f32), ue(21, 2, 1, 0, 300f32), ue(22, 3, 1, 0, -300f32)],
+            vec![ue(30, 1, 2, 0, 0f32), ue(31, 2, 2, 0, 200f32), ue(32, 3, 2, 0, -200f32)],
+            vec![ue(40, 1, 2, 20, 0f32), ue(41, 2, 2, 20, 100f32)],
+        ] {
+            let sim = MultiUeSim::new(&pucch_config, params, &config(ues));
+            assert!(sim.collisions().is_empty());
+            for result in sim.run() {
+                let point = result.point;
+                assert_eq!((point.ack_miss, point.nack_to_ack, point.dtx_to_ack, point.bler), (0f32, 0f32, 0f32, 0f32));
+                assert!(result.isr_db < -15f32, "{:?}", result);
+            }
+        }
+
+        // the same cyclic shifts, the UEs are not separable
+        let sim = MultiUeSim::new(&pucch_config, params, &config(vec![ue(20, 1, 1, 0, 0f32), ue(20, 2, 1, 0, 0f32)]));
+        assert_eq!(sim.collisions(), vec![(0, 1)]);
+        assert!(sim.run().iter().all(|result| result.point.false_alarm > 0.5));
+    }
+
+    #[test]
+    fn multi_ue_files() {
+        let config_file = "target/multi_ue_test_config.json";
+        let ues = vec![ue(7, 1, 2, 0, 0f32), ue(8, 2, 2, 0, 200f32), ue(9, 3, 2, 0, -200f32)];
+        let config = MultiUeConfig { ues, num_trials: 10, combining: Combining::Mrc, dtx_threshold: 0.4, seed: 1 };
+        std::fs::write(config_file, serde_json::to_string(&config).unwrap()).unwrap();
+        let results = run_multi_ue(TEST_PUCCH_CONFIG_FILE, config_file, test_params(0)).unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|result| result.point.bler == 0f32 && result.isr_db < -15f32), "{:?}", results);
+    }
+}
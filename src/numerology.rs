@@ -0,0 +1,202 @@
+use core::fmt;
+use serde_derive::{Deserialize, Serialize};
+
+// 38.211, 4.1, kappa = Ts / Tc
+pub const KAPPA: u32 = 64;
+// 38.211, 4.1, Tc = 1 / (480 kHz * 4096)
+pub const TC: f64 = 1f64 / (480e3 * 4096f64);
+pub const NUM_SYM_PER_SLOT_NORMAL_CP: u32 = 14;
+pub const NUM_SYM_PER_SLOT_EXTENDED_CP: u32 = 12;
+pub const NUM_SUBFRAME_PER_FRAME: u32 = 10;
+pub const NUM_SFN: u32 = 1024;
+
+const SCS_BASE_KHZ: u32 = 15;
+const MAX_MU: u32 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CyclicPrefix {
+    Normal,
+    Extended,
+}
+
+// 38.211, 4.2, subcarrier spacing 2^mu * 15 kHz and cyclic prefix
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Numerology {
+    mu: u32,
+    cyclic_prefix: CyclicPrefix,
+}
+
+impl Numerology {
+    pub fn new(mu: u32, cyclic_prefix: CyclicPrefix) -> Self {
+        assert!(mu <= MAX_MU, "invalid numerology {}!", mu);
+        // 38.211, Table 4.2-1
+        assert!(cyclic_prefix == CyclicPrefix::Normal || mu == 2, "extended CP is only for numerology 2, not {}!", mu);
+        Numerology { mu, cyclic_prefix }
+    }
+
+    pub fn from_scs(scs_khz: u32, cyclic_prefix: CyclicPrefix) -> Self {
+        assert!(scs_khz.is_multiple_of(SCS_BASE_KHZ), "invalid subcarrier spacing {} kHz!", scs_khz);
+        let ratio = scs_khz / SCS_BASE_KHZ;
+        assert!(ratio.is_power_of_two(), "invalid subcarrier spacing {} kHz!", scs_khz);
+        Numerology::new(ratio.trailing_zeros(), cyclic_prefix)
+    }
+
+    #[allow(dead_code)]
+    pub fn mu(&self) -> u32 {
+        self.mu
+    }
+
+    #[allow(dead_code)]
+    pub fn cyclic_prefix(&self) -> CyclicPrefix {
+        self.cyclic_prefix
+    }
+
+    pub fn scs_khz(&self) -> u32 {
+        SCS_BASE_KHZ << self.mu
+    }
+
+    pub fn scs_hz(&self) -> f64 {
+        self.scs_khz() as f64 * 1e3
+    }
+
+    // 38.211, Table 4.3.2-1 and Table 4.3.2-2, N_symb^slot
+    pub fn num_sym_per_slot(&self) -> u32 {
+        match self.cyclic_prefix {
+            CyclicPrefix::Normal => NUM_SYM_PER_SLOT_NORMAL_CP,
+            CyclicPrefix::Extended => NUM_SYM_PER_SLOT_EXTENDED_CP,
+        }
+    }
+
+    pub fn num_slot_per_subframe(&self) -> u32 {
+        1 << self.mu
+    }
+
+    pub fn num_slot_per_frame(&self) -> u32 {
+        NUM_SUBFRAME_PER_FRAME * self.num_slot_per_subframe()
+    }
+
+    pub fn num_sym_per_subframe(&self) -> u32 {
+        self.num_sym_per_slot() * self.num_slot_per_subframe()
+    }
+
+    // average symbol duration including the CP, in seconds
+    pub fn sym_duration_s(&self) -> f64 {
+        1e-3 / self.num_sym_per_subframe() as f64
+    }
+
+    // 38.211, 5.3.1, N_u in Tc
+    pub fn sym_len_tc(&self) -> u32 {
+        (2048 * KAPPA) >> self.mu
+    }
+
+    // 38.211, 5.3.1, N_CP,l in Tc, l is the symbol index in the subframe
+    // the normal CP is 16 kappa longer at l = 0 and l = 7 * 2^mu, i.e. every 0.5 ms
+    pub fn cp_len_tc(&self, l: u32) -> u32 {
+        match self.cyclic_prefix {
+            CyclicPrefix::Extended => (512 * KAPPA) >> self.mu,
+            CyclicPrefix::Normal if l.is_multiple_of(7 << self.mu) => ((144 * KAPPA) >> self.mu) + 16 * KAPPA,
+            CyclicPrefix::Normal => (144 * KAPPA) >> self.mu,
+        }
+    }
+}
+
+impl Default for Numerology {
+    fn default() -> Self {
+        Numerology::new(0, CyclicPrefix::Normal)
+    }
+}
+
+// system frame number and slot number in the frame, wraps around after SFN 1023
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlotTime {
+    numerology: Numerology,
+    sfn: u32,
+    slot: u32, // n_s,f^mu
+}
+
+impl SlotTime {
+    pub fn new(numerology: Numerology, sfn: u32, slot: u32) -> Self {
+        assert!(sfn < NUM_SFN, "invalid SFN {}!", sfn);
+        assert!(slot < numerology.num_slot_per_frame(), "invalid slot {}!", slot);
+        SlotTime { numerology, sfn, slot }
+    }
+
+    #[allow(dead_code)]
+    pub fn numerology(&self) -> Numerology {
+        self.numerology
+    }
+
+    #[allow(dead_code)]
+    pub fn sfn(&self) -> u32 {
+        self.sfn
+    }
+
+    // n_s,f^mu, slot number in the frame
+    pub fn slot(&self) -> u32 {
+        self.slot
+    }
+
+    #[allow(dead_code)]
+    pub fn subframe(&self) -> u32 {
+        self.slot / self.numerology.num_slot_per_subframe()
+    }
+
+    // slot count from SFN 0 slot 0
+    fn slot_count(&self) -> u32 {
+        self.sfn * self.numerology.num_slot_per_frame() + self.slot
+    }
+
+    fn num_slot_per_period(&self) -> u32 {
+        NUM_SFN * self.numerology.num_slot_per_frame()
+    }
+
+    pub fn add_slots(&self, num_slots: u32) -> Self {
+        let slot_count = ((self.slot_count() as u64 + num_slots as u64) % self.num_slot_per_period() as u64) as u32;
+        let num_slot_per_frame = self.numerology.num_slot_per_frame();
+        SlotTime::new(self.numerology, slot_count / num_slot_per_frame, slot_count % num_slot_per_frame)
+    }
+
+    // number of slots from earlier to self, with the SFN wraparound
+    #[allow(dead_code)]
+    pub fn slots_since(&self, earlier: &SlotTime) -> u32 {
+        assert!(self.numerology == earlier.numerology, "numerology mismatch!");
+        let period = self.num_slot_per_period();
+        (self.slot_count() + period - earlier.slot_count()) % period
+    }
+}
+
+impl fmt::Display for SlotTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.sfn, self.slot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numerology() {
+        let numerology = Numerology::from_scs(120, CyclicPrefix::Normal);
+        assert_eq!((numerology.mu(), numerology.num_slot_per_frame(), numerology.num_sym_per_slot()), (3, 80, 14));
+        let numerology = Numerology::from_scs(60, CyclicPrefix::Extended);
+        assert_eq!((numerology.mu(), numerology.num_sym_per_subframe(), numerology.cp_len_tc(5)), (2, 48, 8192));
+        // 1 ms subframe of 30 kHz
+        let numerology = Numerology::new(1, CyclicPrefix::Normal);
+        let subframe_tc =
+            (0..numerology.num_sym_per_subframe()).map(|l| numerology.sym_len_tc() + numerology.cp_len_tc(l)).sum::<u32>();
+        assert_eq!(subframe_tc, 480 * 4096);
+    }
+
+    #[test]
+    fn slot_time_wraparound() {
+        let numerology = Numerology::new(1, CyclicPrefix::Normal);
+        let time = SlotTime::new(numerology, 1023, 18);
+        let next = time.add_slots(3);
+        assert_eq!((next.sfn(), next.slot(), next.subframe()), (0, 1, 0));
+        assert_eq!(next.slots_since(&time), 3);
+        assert_eq!(time.slots_since(&next), 1024 * 20 - 3);
+        assert_eq!(time.add_slots(1024 * 20), time);
+        assert_eq!(format!("{}", next), "0.1");
+    }
+}
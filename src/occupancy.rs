@@ -0,0 +1,210 @@
+use crate::constants::*;
+use crate::math::mask;
+use crate::pucch::*;
+use crate::rrc_pucch::*;
+use crate::sequence::*;
+
+// PRB range x symbol range of one hop, the symbols are in the slot
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PucchRegion {
+    pub n_hop: u32,
+    pub start_prb: u32,
+    pub num_rb: u32,
+    pub start_sym: u32,
+    pub num_sym: u32,
+}
+
+// code-domain dimension of the PUCCH multiplexing on the same PRB
+#[derive(Debug, Clone, PartialEq)]
+pub enum PucchCode {
+    CyclicShift(Vec<u32>),                          // format 0, m_0 + m_CS of all the possible UCI
+    CyclicShiftOcc { cyclic_shift: u32, occ: u32 }, // format 1, m_0 and time domain OCC index
+    PreDftOcc { occ_len: u32, occ_idx: u32 },       // format 4
+    Exclusive,                                      // format 2 and 3, no multiplexing
+}
+
+// time-frequency-code occupancy of a PUCCH transmission of one UE in a slot
+// the UEs are in the same cell with the same hopping config, so the base sequences of a hop are the same
+#[derive(Debug, Clone, PartialEq)]
+pub struct PucchOccupancy {
+    pub regions: Vec<PucchRegion>,
+    pub code: PucchCode,
+}
+
+impl PucchRegion {
+    fn is_overlap(&self, region: &PucchRegion) -> bool {
+        fn is_range_overlap(start_a: u32, len_a: u32, start_b: u32, len_b: u32) -> bool {
+            start_a < start_b + len_b && start_b < start_a + len_a
+        }
+        is_range_overlap(self.start_prb, self.num_rb, region.start_prb, region.num_rb)
+            && is_range_overlap(self.start_sym, self.num_sym, region.start_sym, region.num_sym)
+    }
+}
+
+impl PucchOccupancy {
+    // slot_idx is the slot index in a multi-slot PUCCH, o_harq and sr are the format 0 payload
+    pub fn new(pucch_config: &PucchConfig, pucch_resource: &PucchResource, slot_idx: u32, o_harq: u32, sr: bool) -> Self {
+        let pucch_formats = &pucch_config.pucch_formats;
+        let (start_sym, num_sym) = pucch_resource.occupied_sym();
+        let mut regions: Vec<PucchRegion> = Vec::new();
+        for l in 0..num_sym {
+            let n_hop = pucch_resource.n_hop(pucch_formats, l);
+            let start_prb = pucch_resource.sym_start_prb(pucch_formats, l, slot_idx);
+            match regions.last_mut() {
+                Some(region) if region.n_hop == n_hop && region.start_prb == start_prb => region.num_sym += 1,
+                _ => regions.push(PucchRegion {
+                    n_hop,
+                    start_prb,
+                    num_rb: pucch_resource.num_rb(),
+                    start_sym: start_sym + l,
+                    num_sym: 1,
+                }),
+            }
+        }
+
+        let code = match pucch_resource.format {
+            PucchFormat::Format0 { init_cyclic_shift, .. } => {
+                PucchCode::CyclicShift(format0_cyclic_shifts(init_cyclic_shift, o_harq, sr))
+            }
+            PucchFormat::Format1 { init_cyclic_shift, time_occ, .. } => {
+                PucchCode::CyclicShiftOcc { cyclic_shift: init_cyclic_shift, occ: time_occ }
+            }
+            PucchFormat::Format4 { occ_len, occ_idx, .. } => PucchCode::PreDftOcc { occ_len, occ_idx },
+            PucchFormat::Format2 { .. } | PucchFormat::Format3 { .. } => PucchCode::Exclusive,
+        };
+
+        PucchOccupancy { regions, code }
+    }
+
+    // symbols of the slot occupied in any of the hops
+    pub fn sym_bitmap(&self) -> u32 {
+        self.regions.iter().fold(0u32, |bitmap, region| bitmap | mask(region.start_sym, region.num_sym))
+    }
+
+    // 38.213, 9.2.5, two PUCCH of the same UE overlap if they share a symbol, regardless of the PRBs and the codes
+    pub fn is_time_overlap(&self, occupancy: &PucchOccupancy) -> bool {
+        (self.sym_bitmap() & occupancy.sym_bitmap()) != 0
+    }
+
+    // two PUCCH of different UEs collide if they overlap in time and frequency and are not orthogonal in code
+    pub fn is_collision(&self, occupancy: &PucchOccupancy, hopping: &PucchGroupSeqHopping) -> bool {
+        let overlaps = self
+            .regions
+            .iter()
+            .flat_map(|a| occupancy.regions.iter().filter(move |b| a.is_overlap(b)).map(move |b| (a, b)))
+            .collect::<Vec<_>>();
+        if overlaps.is_empty() {
+            return false;
+        }
+
+        // 38.211, 6.3.2.2.1, with group or sequence hopping, the base sequence of the hops are different
+        let same_base_seq = *hopping == PucchGroupSeqHopping::Neither || overlaps.iter().all(|(a, b)| a.n_hop == b.n_hop);
+        // the orthogonal cover codes only hold on identical symbols and PRBs
+        let same_regions = self.regions == occupancy.regions;
+
+        let is_orthogonal = match (&self.code, &occupancy.code) {
+            (PucchCode::Exclusive, _) | (_, PucchCode::Exclusive) => false,
+            (
+                PucchCode::PreDftOcc { occ_len: len_a, occ_idx: idx_a },
+                PucchCode::PreDftOcc { occ_len: len_b, occ_idx: idx_b },
+            ) => same_regions && len_a == len_b && idx_a != idx_b,
+            (PucchCode::PreDftOcc { .. }, _) | (_, PucchCode::PreDftOcc { .. }) => false,
+            (
+                PucchCode::CyclicShiftOcc { cyclic_shift: cs_a, occ: occ_a },
+                PucchCode::CyclicShiftOcc { cyclic_shift: cs_b, occ: occ_b },
+            ) if cs_a == cs_b => same_base_seq && same_regions && occ_a != occ_b,
+            // the cyclic shifts of the same base sequence are orthogonal in each symbol
+            (a, b) => same_base_seq && a.cyclic_shifts().iter().all(|cs| !b.cyclic_shifts().contains(cs)),
+        };
+        !is_orthogonal
+    }
+}
+
+impl PucchCode {
+    fn cyclic_shifts(&self) -> Vec<u32> {
+        match self {
+            PucchCode::CyclicShift(cyclic_shifts) => cyclic_shifts.clone(),
+            PucchCode::CyclicShiftOcc { cyclic_shift, .. } => vec![*cyclic_shift],
+            _ => panic!("impossible to be here!"),
+        }
+    }
+}
+
+// m_0 + m_CS of the HARQ-ACK values, with the positive SR ones if sr
+fn format0_cyclic_shifts(m0: u32, o_harq: u32, sr: bool) -> Vec<u32> {
+    let mut cyclic_shifts = format0_uci_candidates(o_harq, sr)
+        .iter()
+        .map(|(harq_ack, sr)| (m0 + format0_m_cs(harq_ack, *sr)) % NUM_SC_PER_RB)
+        .collect::<Vec<_>>();
+    cyclic_shifts.sort_unstable();
+    cyclic_shifts.dedup();
+    cyclic_shifts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    fn occupancy(pucch_config: &PucchConfig, id: u32, o_harq: u32) -> PucchOccupancy {
+        let resource = PucchResourceId::new(pucch_config, id).pucch_resource(pucch_config);
+        PucchOccupancy::new(pucch_config, resource, 0, o_harq, false)
+    }
+
+    #[test]
+    fn collision() {
+        let mut resources = Vec::new();
+        let mut push = |pucch_resource_id, start_prb, intra_slot_freq_hopping, format| {
+            resources.push(PucchResourceR { pucch_resource_id, start_prb, intra_slot_freq_hopping, format })
+        };
+        // format 0 at PRB 5, 1 HARQ bit, initial cyclic shift 0 / 3 / 6
+        for (id, init_cyclic_shift) in [(10, 0), (11, 3), (12, 6)] {
+            push(
+                id,
+                5,
+                IntraSlotFreqHopping::NoHopping,
+                PucchFormat::Format0 { init_cyclic_shift, num_sym: 2, start_sym: 12 },
+            );
+        }
+        // format 1 with hopping from PRB 8 to 30, OCC 0 / 1, and OCC 0 from symbol 1
+        let hopping = IntraSlotFreqHopping::Hopping { second_prb: 30 };
+        for (id, time_occ, start_sym) in [(20, 0, 0), (21, 1, 0), (22, 0, 1)] {
+            push(id, 8, hopping, PucchFormat::Format1 { init_cyclic_shift: 0, num_sym: 12, start_sym, time_occ });
+        }
+        // format 4 on PRB 40, format 3 on PRB 39 .. 40
+        for (id, occ_idx) in [(30, 0), (31, 1)] {
+            push(
+                id,
+                40,
+                IntraSlotFreqHopping::NoHopping,
+                PucchFormat::Format4 { num_sym: 14, occ_len: 2, occ_idx, start_sym: 0 },
+            );
+        }
+        push(32, 39, IntraSlotFreqHopping::NoHopping, PucchFormat::Format3 { num_rb: 2, num_sym: 4, start_sym: 10 });
+        let pucch_config = test_pucch_config(&resources);
+        let hopping = pucch_config.pucch_group_seq_hopping;
+
+        let f1 = occupancy(&pucch_config, 20, 0);
+        assert_eq!(
+            f1.regions.iter().map(|r| (r.start_prb, r.start_sym, r.num_sym)).collect::<Vec<_>>(),
+            [(8, 0, 6), (30, 6, 6)]
+        );
+        // {0, 6} against {3, 9} and {6, 0}
+        assert!(!occupancy(&pucch_config, 10, 1).is_collision(&occupancy(&pucch_config, 11, 1), &hopping));
+        assert!(occupancy(&pucch_config, 10, 1).is_collision(&occupancy(&pucch_config, 12, 1), &hopping));
+        // 2 HARQ bits on {0, 3, 6, 9} and {3, 6, 9, 0}
+        assert!(occupancy(&pucch_config, 10, 2).is_collision(&occupancy(&pucch_config, 11, 1), &hopping));
+        assert!(!f1.is_collision(&occupancy(&pucch_config, 21, 0), &hopping));
+        // the OCC does not hold on misaligned symbols
+        assert!(f1.is_collision(&occupancy(&pucch_config, 22, 0), &hopping));
+        assert!(!occupancy(&pucch_config, 30, 0).is_collision(&occupancy(&pucch_config, 31, 0), &hopping));
+        assert!(occupancy(&pucch_config, 30, 0).is_collision(&occupancy(&pucch_config, 32, 0), &hopping));
+        assert!(!occupancy(&pucch_config, 10, 1).is_collision(&occupancy(&pucch_config, 30, 0), &hopping));
+
+        // the same UE, the PRBs and the codes do not matter
+        assert!(occupancy(&pucch_config, 10, 1).is_time_overlap(&occupancy(&pucch_config, 30, 0)));
+        assert!(f1.is_time_overlap(&occupancy(&pucch_config, 32, 0)));
+        assert!(!f1.is_time_overlap(&occupancy(&pucch_config, 10, 1)));
+        assert_eq!(f1.sym_bitmap(), 0xfff);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::complex::*;
+use crate::constants::*;
+use crate::dft::*;
+use crate::err::Error;
+use crate::grid::ResourceGrid;
+use crate::numerology::*;
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+
+// 38.211, 5.3.1 and 5.4, CP-OFDM of one carrier, the samples are at fft_size * SCS
+#[derive(Debug, Clone)]
+pub struct Ofdm {
+    numerology: Numerology,
+    fft_size: u32,
+    num_rb: u32,
+    center_freq_hz: f64, // f0 of the upconversion, 0 disables the phase compensation
+}
+
+impl Ofdm {
+    pub fn new(numerology: Numerology, fft_size: u32, num_rb: u32, center_freq_hz: f64) -> Self {
+        assert!(num_rb * NUM_SC_PER_RB <= fft_size, "FFT size {} less than {} RBs!", fft_size, num_rb);
+        // the CP lengths are integer numbers of samples
+        assert!(fft_size.is_multiple_of(128), "invalid FFT size {}!", fft_size);
+        Ofdm { numerology, fft_size, num_rb, center_freq_hz }
+    }
+
+    pub fn numerology(&self) -> Numerology {
+        self.numerology
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.numerology.scs_hz() * self.fft_size as f64
+    }
+
+    fn tc_to_samples(&self, len_tc: u32) -> u32 {
+        (len_tc as u64 * self.fft_size as u64 / self.numerology.sym_len_tc() as u64) as u32
+    }
+
+    // CP length in samples, l is the symbol index in the subframe
+    pub fn cp_len(&self, l: u32) -> u32 {
+        self.tc_to_samples(self.numerology.cp_len_tc(l))
+    }
+
+    // t_start,l in Tc from the start of the subframe
+    fn sym_start_tc(&self, l: u32) -> u64 {
+        (0..l).map(|i| (self.numerology.sym_len_tc() + self.numerology.cp_len_tc(i)) as u64).sum()
+    }
+
+    // number of samples of the slot, slot is the slot number in the frame
+    pub fn slot_len(&self, slot: u32) -> usize {
+        let first_sym = self.first_sym_in_subframe(slot);
+        (first_sym..first_sym + self.numerology.num_sym_per_slot()).map(|l| (self.fft_size + self.cp_len(l)) as usize).sum()
+    }
+
+    fn first_sym_in_subframe(&self, slot: u32) -> u32 {
+        (slot % self.numerology.num_slot_per_subframe()) * self.numerology.num_sym_per_slot()
+    }
+
+    // 38.211, 5.4, e^(-j * 2 * pi * f0 * (t_start,l + N_CP,l * Tc)), the baseband equivalent of the upconversion
+    fn phase_compensation(&self, l: u32) -> Complex {
+        let num_tc = self.sym_start_tc(l) + self.numerology.cp_len_tc(l) as u64;
+        let cycles = (self.center_freq_hz * TC * num_tc as f64).fract();
+        Complex::expj((-2f64 * PI * cycles) as f32)
+    }
+
+    // FFT bin of subcarrier k, the grid is centered at DC, k - N_grid * N_sc_rb / 2
+    fn fft_bin(&self, k: u32) -> usize {
+        let offset = k as i64 - (self.num_rb * NUM_SC_PER_RB / 2) as i64;
+        offset.rem_euclid(self.fft_size as i64) as usize
+    }
+
+    // 38.211, 5.3.1, time domain samples of a slot, normalized by 1 / sqrt(fft_size)
+    pub fn modulate(&self, grid: &ResourceGrid, slot: u32) -> Vec<Complex> {
+        assert!(grid.num_rb() == self.num_rb && grid.num_sym() == self.numerology.num_sym_per_slot(), "grid size mismatch!");
+        let scale = 1f32 / (self.fft_size as f32).sqrt();
+        let first_sym = self.first_sym_in_subframe(slot);
+        let mut samples = Vec::with_capacity(self.slot_len(slot));
+        for sym in 0..grid.num_sym() {
+            let l = first_sym + sym;
+            let mut freq = vec![Complex::ZERO; self.fft_size as usize];
+            for (k, &re) in grid.symbol(sym).iter().enumerate() {
+                freq[self.fft_bin(k as u32)] = re;
+            }
+            let phase = self.phase_compensation(l) * scale;
+            let time = idft(&freq).into_iter().map(|x| x * phase).collect::<Vec<_>>();
+            let cp_len = self.cp_len(l) as usize;
+            samples.extend_from_slice(&time[time.len() - cp_len..]);
+            samples.extend(time);
+        }
+        samples
+    }
+
+    // inverse of modulate, samples start at the first sample of the slot
+    pub fn demodulate(&self, samples: &[Complex], slot: u32) -> ResourceGrid {
+        assert!(samples.len() >= self.slot_len(slot), "{} samples less than a slot!", samples.len());
+        let scale = 1f32 / (self.fft_size as f32).sqrt();
+        let first_sym = self.first_sym_in_subframe(slot);
+        let mut grid = ResourceGrid::new(self.num_rb, self.numerology.num_sym_per_slot());
+        let mut offset = 0;
+        for sym in 0..grid.num_sym() {
+            let l = first_sym + sym;
+            offset += self.cp_len(l) as usize;
+            let freq = dft(&samples[offset..offset + self.fft_size as usize]);
+            offset += self.fft_size as usize;
+            let phase = self.phase_compensation(l).conj() * scale;
+            for k in 0..grid.num_sc() {
+                grid.set(k, sym, freq[self.fft_bin(k)] * phase);
+            }
+        }
+        grid
+    }
+}
+
+// interleaved I/Q of 32 bit little-endian float, cf32_le
+pub fn write_cf32(file_name: &str, samples: &[Complex]) -> Result<(), Error> {
+    let mut f = BufWriter::new(File::create(file_name)?);
+    for x in samples {
+        f.write_all(&x.re.to_le_bytes())?;
+        f.write_all(&x.im.to_le_bytes())?;
+    }
+    f.flush()?;
+    Ok(())
+}
+
+pub fn read_cf32(file_name: &str) -> Result<Vec<Complex>, Error> {
+    let mut bytes = Vec::new();
+    BufReader::new(File::open(file_name)?).read_to_end(&mut bytes)?;
+    if !bytes.len().is_multiple_of(8) {
+        return Err(Error::Nr("cf32 file length not a multiple of 8!"));
+    }
+    let samples = bytes
+        .chunks(8)
+        .map(|b| Complex::new(f32::from_le_bytes([b[0], b[1], b[2], b[3]]), f32::from_le_bytes([b[4], b[5], b[6], b[7]])))
+        .collect();
+    Ok(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_grid(num_rb: u32, num_sym: u32) -> ResourceGrid {
+        let mut grid = ResourceGrid::new(num_rb, num_sym);
+        for l in 0..num_sym {
+            for k in 0..grid.num_sc() {
+                grid.set(k, l, Complex::expj((k * 7 + l * 3) as f32 * 0.1));
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn cp_len() {
+        // 30 kHz, 4096 FFT: 288 samples, the first symbol of each half subframe 352 samples
+        let ofdm = Ofdm::new(Numerology::new(1, CyclicPrefix::Normal), 4096, 273, 0f64);
+        assert_eq!(ofdm.sample_rate(), 122.88e6);
+        assert_eq!((ofdm.cp_len(0), ofdm.cp_len(1), ofdm.cp_len(14), ofdm.cp_len(27)), (352, 288, 352, 288));
+        // 0.5 ms in each slot
+        assert_eq!((ofdm.slot_len(0), ofdm.slot_len(1)), (61440, 61440));
+
+        let ofdm = Ofdm::new(Numerology::new(0, CyclicPrefix::Normal), 2048, 106, 0f64);
+        assert_eq!((ofdm.cp_len(0), ofdm.cp_len(1), ofdm.cp_len(7), ofdm.cp_len(8)), (160, 144, 160, 144));
+        assert_eq!(ofdm.slot_len(0), 30720);
+
+        let ofdm = Ofdm::new(Numerology::new(2, CyclicPrefix::Extended), 1024, 24, 0f64);
+        assert_eq!((ofdm.cp_len(0), ofdm.cp_len(13)), (256, 256));
+        assert_eq!(ofdm.slot_len(3) * 4, 61440);
+    }
+
+    #[test]
+    fn modulate_loopback() {
+        for (mu, fft_size, num_rb, cyclic_prefix, slot) in [
+            (0, 256, 15, CyclicPrefix::Normal, 0),
+            (1, 512, 24, CyclicPrefix::Normal, 3),
+            (2, 256, 20, CyclicPrefix::Extended, 2),
+            (6, 2048, 32, CyclicPrefix::Normal, 63),
+        ] {
+            let ofdm = Ofdm::new(Numerology::new(mu, cyclic_prefix), fft_size, num_rb, 3.5e9);
+            let grid = test_grid(num_rb, ofdm.numerology().num_sym_per_slot());
+            let samples = ofdm.modulate(&grid, slot);
+            assert_eq!(samples.len(), ofdm.slot_len(slot));
+            let rx_grid = ofdm.demodulate(&samples, slot);
+            for l in 0..grid.num_sym() {
+                assert!(grid.symbol(l).iter().zip(rx_grid.symbol(l)).all(|(&a, &b)| (a - b).abs() < 1e-3), "mu {}", mu);
+            }
+        }
+    }
+}
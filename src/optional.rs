@@ -9,6 +9,7 @@ pub struct Optional<T> {
     default_val: Option<T>,
 }
 
+#[allow(dead_code)]
 impl<T: PartialEq> Optional<T> {
     pub fn check(&self) -> Result<(), Error> {
         if let Some(val) = &self.val {
@@ -0,0 +1,454 @@
+use crate::crc::*;
+use crate::math::*;
+
+// 38.212, Table 5.3.1.2-1, polar sequence Q_0^(N_max - 1), in ascending order of reliability
+#[rustfmt::skip]
+const POLAR_SEQ: [u16; 1024] = [
+    0, 1, 2, 4, 8, 16, 32, 3, 5, 64, 9, 6, 17, 10, 18, 128, 12, 33, 65, 20, 256, 34, 24, 36, 7, 129, 66, 512, 11, 40, 68, 130,
+    19, 13, 48, 14, 72, 257, 21, 132, 35, 258, 26, 513, 80, 37, 25, 22, 136, 260, 264, 38, 514, 96, 67, 41, 144, 28, 69, 42, 516, 49, 74, 272,
+    160, 520, 288, 528, 192, 544, 70, 44, 131, 81, 50, 73, 15, 320, 133, 52, 23, 134, 384, 76, 137, 82, 56, 27, 97, 39, 259, 84, 138, 145, 261, 29,
+    43, 98, 515, 88, 140, 30, 146, 71, 262, 265, 161, 576, 45, 100, 640, 51, 148, 46, 75, 266, 273, 517, 104, 162, 53, 193, 152, 77, 164, 768, 268, 274,
+    518, 54, 83, 57, 521, 112, 135, 78, 289, 194, 85, 276, 522, 58, 168, 139, 99, 86, 60, 280, 89, 290, 529, 524, 196, 141, 101, 147, 176, 142, 530, 321,
+    31, 200, 90, 545, 292, 322, 532, 263, 149, 102, 105, 304, 296, 163, 92, 47, 267, 385, 546, 324, 208, 386, 150, 153, 165, 106, 55, 328, 536, 577, 548, 113,
+    154, 79, 269, 108, 578, 224, 166, 519, 552, 195, 270, 641, 523, 275, 580, 291, 59, 169, 560, 114, 277, 156, 87, 197, 116, 170, 61, 531, 525, 642, 281, 278,
+    526, 177, 293, 388, 91, 584, 769, 198, 172, 120, 201, 336, 62, 282, 143, 103, 178, 294, 93, 644, 202, 592, 323, 392, 297, 770, 107, 180, 151, 209, 284, 648,
+    94, 204, 298, 400, 608, 352, 325, 533, 155, 210, 305, 547, 300, 109, 184, 534, 537, 115, 167, 225, 326, 306, 772, 157, 656, 329, 110, 117, 212, 171, 776, 330,
+    226, 549, 538, 387, 308, 216, 416, 271, 279, 158, 337, 550, 672, 118, 332, 579, 540, 389, 173, 121, 553, 199, 784, 179, 228, 338, 312, 704, 390, 174, 554, 581,
+    393, 283, 122, 448, 353, 561, 203, 63, 340, 394, 527, 582, 556, 181, 295, 285, 232, 124, 205, 182, 643, 562, 286, 585, 299, 354, 211, 401, 185, 396, 344, 586,
+    645, 593, 535, 240, 206, 95, 327, 564, 800, 402, 356, 307, 301, 417, 213, 568, 832, 588, 186, 646, 404, 227, 896, 594, 418, 302, 649, 771, 360, 539, 111, 331,
+    214, 309, 188, 449, 217, 408, 609, 596, 551, 650, 229, 159, 420, 310, 541, 773, 610, 657, 333, 119, 600, 339, 218, 368, 652, 230, 391, 313, 450, 542, 334, 233,
+    555, 774, 175, 123, 658, 612, 341, 777, 220, 314, 424, 395, 673, 583, 355, 287, 183, 234, 125, 557, 660, 616, 342, 316, 241, 778, 563, 345, 452, 397, 403, 207,
+    674, 558, 785, 432, 357, 187, 236, 664, 624, 587, 780, 705, 126, 242, 565, 398, 346, 456, 358, 405, 303, 569, 244, 595, 189, 566, 676, 361, 706, 589, 215, 786,
+    647, 348, 419, 406, 464, 680, 801, 362, 590, 409, 570, 788, 597, 572, 219, 311, 708, 598, 601, 651, 421, 792, 802, 611, 602, 410, 231, 688, 653, 248, 369, 190,
+    364, 654, 659, 335, 480, 315, 221, 370, 613, 422, 425, 451, 614, 543, 235, 412, 343, 372, 775, 317, 222, 426, 453, 237, 559, 833, 804, 712, 834, 661, 808, 779,
+    617, 604, 433, 720, 816, 836, 347, 897, 243, 662, 454, 318, 675, 618, 898, 781, 376, 428, 665, 736, 567, 840, 625, 238, 359, 457, 399, 787, 591, 678, 434, 677,
+    349, 245, 458, 666, 620, 363, 127, 191, 782, 407, 436, 626, 571, 465, 681, 246, 707, 350, 599, 668, 790, 460, 249, 682, 573, 411, 803, 789, 709, 365, 440, 628,
+    689, 374, 423, 466, 793, 250, 371, 481, 574, 413, 603, 366, 468, 655, 900, 805, 615, 684, 710, 429, 794, 252, 373, 605, 848, 690, 713, 632, 482, 806, 427, 904,
+    414, 223, 663, 692, 835, 619, 472, 455, 796, 809, 714, 721, 837, 716, 864, 810, 606, 912, 722, 696, 377, 435, 817, 319, 621, 812, 484, 430, 838, 667, 488, 239,
+    378, 459, 622, 627, 437, 380, 818, 461, 496, 669, 679, 724, 841, 629, 351, 467, 438, 737, 251, 462, 442, 441, 469, 247, 683, 842, 738, 899, 670, 783, 849, 820,
+    728, 928, 791, 367, 901, 630, 685, 844, 633, 711, 253, 691, 824, 902, 686, 740, 850, 375, 444, 470, 483, 415, 485, 905, 795, 473, 634, 744, 852, 960, 865, 693,
+    797, 906, 715, 807, 474, 636, 694, 254, 717, 575, 913, 798, 811, 379, 697, 431, 607, 489, 866, 723, 486, 908, 718, 813, 476, 856, 839, 725, 698, 914, 752, 868,
+    819, 814, 439, 929, 490, 623, 671, 739, 916, 463, 843, 381, 497, 930, 821, 726, 961, 872, 492, 631, 729, 700, 443, 741, 845, 920, 382, 822, 851, 730, 498, 880,
+    742, 445, 471, 635, 932, 687, 903, 825, 500, 846, 745, 826, 732, 446, 962, 936, 475, 853, 867, 637, 907, 487, 695, 746, 828, 753, 854, 857, 504, 799, 255, 964,
+    909, 719, 477, 915, 638, 748, 944, 869, 491, 699, 754, 858, 478, 968, 383, 910, 815, 976, 870, 917, 727, 493, 873, 701, 931, 756, 860, 499, 731, 823, 922, 874,
+    918, 502, 933, 743, 760, 881, 494, 702, 921, 501, 876, 847, 992, 447, 733, 827, 934, 882, 937, 963, 747, 505, 855, 924, 734, 829, 965, 938, 884, 506, 749, 945,
+    966, 755, 859, 940, 830, 911, 871, 639, 888, 479, 946, 750, 969, 508, 861, 757, 970, 919, 875, 862, 758, 948, 977, 923, 972, 761, 877, 952, 495, 703, 935, 978,
+    883, 762, 503, 925, 878, 735, 993, 885, 939, 994, 980, 926, 764, 941, 967, 886, 831, 947, 507, 889, 984, 751, 942, 996, 971, 890, 509, 949, 973, 1000, 892, 950,
+    863, 759, 1008, 510, 979, 953, 763, 974, 954, 879, 981, 982, 927, 995, 765, 956, 887, 985, 997, 986, 943, 891, 998, 766, 511, 988, 1001, 951, 1002, 893, 975, 894,
+    1009, 955, 1004, 1010, 957, 983, 958, 987, 1012, 999, 1016, 767, 989, 1003, 990, 1005, 959, 1011, 1013, 895, 1006, 1014, 1017, 1018, 991, 1020, 1007, 1015, 1019, 1021, 1022, 1023,
+];
+
+// 38.212, Table 5.4.1.1-1, sub-block interleaver pattern P(i)
+#[rustfmt::skip]
+const SUB_BLOCK_INTERLEAVER_PATTERN: [usize; 32] = [0, 1, 2, 4, 3, 5, 6, 7, 8, 16, 9, 17, 10, 18, 11, 19, 12, 20, 13, 21, 14, 22, 15, 23, 24, 25, 26, 28, 27, 29, 30, 31];
+
+const POLAR_N_MIN: u32 = 5;
+const POLAR_N_MAX_UL: u32 = 10;
+// shortened bits are known to be 0, give them a large positive LLR
+const SHORTENED_LLR: f32 = 1e6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PolarBitType {
+    Frozen,
+    Info,
+    ParityCheck,
+}
+
+// 38.212, 5.4.1.2
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PolarRateMatch {
+    Repetition,
+    Puncturing,
+    Shortening,
+}
+
+// one polar code block, 38.212 5.3.1 and 5.4.1 with the UCI settings I_IL = 0, I_BIL = 1
+#[derive(Debug)]
+pub struct PolarCode {
+    k: usize,
+    e: usize,
+    n: usize, // N = 2^n
+    bit_type: Vec<PolarBitType>,
+    rate_match: PolarRateMatch,
+    sub_block_interleaver: Vec<usize>, // y_n = d_J(n)
+    channel_interleaver: Vec<usize>,   // f_k = e_(channel_interleaver[k])
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolarDecoded {
+    pub bits: Vec<u8>, // CRC removed
+    pub crc_pass: bool,
+}
+
+// UCI of A >= 12 bits, 38.212 6.3.1.2.1, 6.3.1.3.1 and 6.3.1.4.1
+#[derive(Debug)]
+pub struct UciPolar {
+    a: usize,
+    a_prime: usize,
+    num_cb: usize,
+    crc_type: CrcType,
+    code: PolarCode,
+}
+
+// SC decoding state of one path in the list
+#[derive(Debug, Clone)]
+struct ScPath {
+    llr: Vec<Vec<f32>>,             // llr[lambda] has 2^(n - lambda) entries, lambda = 0 is the channel
+    partial_sum: Vec<Vec<[u8; 2]>>, // partial_sum[lambda][beta][phi % 2]
+    u: Vec<u8>,
+    pc_reg: [u8; 5],
+    metric: f32, // the smaller, the more likely
+}
+
+impl PolarCode {
+    pub fn new(k: u32, e: u32) -> Self {
+        assert!(k > 0 && k <= e, "invalid polar code K {}, E {}!", k, e);
+
+        // 38.212, 5.3.1
+        let ceil_log2_e = ceil_log2(e);
+        let n1 = if 8 * e <= 9 * (1 << (ceil_log2_e - 1)) && 16 * k < 9 * e { ceil_log2_e - 1 } else { ceil_log2_e };
+        let n2 = ceil_log2(8 * k); // R_min = 1/8
+        let n = n1.min(n2).clamp(POLAR_N_MIN, POLAR_N_MAX_UL) as usize;
+        let (k, e, big_n) = (k as usize, e as usize, 1usize << n);
+        assert!(k < big_n, "polar code K {} exceeds N {}!", k, big_n);
+
+        let rate_match = if e >= big_n {
+            PolarRateMatch::Repetition
+        } else if 16 * k <= 7 * e {
+            PolarRateMatch::Puncturing
+        } else {
+            PolarRateMatch::Shortening
+        };
+
+        // 38.212, 5.4.1.1
+        let sub_block_len = big_n / 32;
+        let sub_block_interleaver = (0..big_n)
+            .map(|i| SUB_BLOCK_INTERLEAVER_PATTERN[32 * i / big_n] * sub_block_len + i % sub_block_len)
+            .collect::<Vec<_>>();
+
+        // 38.212, 5.3.1.2, Q_F,tmp
+        let mut pre_frozen = vec![false; big_n];
+        match rate_match {
+            PolarRateMatch::Puncturing => {
+                sub_block_interleaver[..big_n - e].iter().for_each(|&j| pre_frozen[j] = true);
+                let num_low =
+                    if 4 * e >= 3 * big_n { (3 * big_n - 2 * e).div_ceil(4) } else { (9 * big_n - 4 * e).div_ceil(16) };
+                pre_frozen[..num_low].fill(true);
+            }
+            PolarRateMatch::Shortening => sub_block_interleaver[e..].iter().for_each(|&j| pre_frozen[j] = true),
+            PolarRateMatch::Repetition => {}
+        }
+
+        // 38.212, 6.3.1.3.1, parity check bits for UCI
+        let (num_pc, num_pc_wm) = if (18..=25).contains(&k) { (3, if e - k + 3 > 192 { 1 } else { 0 }) } else { (0, 0) };
+
+        let candidates = POLAR_SEQ.iter().map(|&i| i as usize).filter(|&i| i < big_n && !pre_frozen[i]).collect::<Vec<_>>();
+        assert!(candidates.len() >= k + num_pc, "not enough polar bit channels for K {}, E {}!", k, e);
+        // Q_I in ascending order of reliability
+        let info_pos = &candidates[candidates.len() - (k + num_pc)..];
+
+        let mut bit_type = vec![PolarBitType::Frozen; big_n];
+        info_pos.iter().for_each(|&i| bit_type[i] = PolarBitType::Info);
+        // (n_PC - n_PC^wm) parity check bits on the least reliable positions of Q_I
+        info_pos[..num_pc - num_pc_wm].iter().for_each(|&i| bit_type[i] = PolarBitType::ParityCheck);
+        if num_pc_wm > 0 {
+            // minimum row weight among the (|Q_I| - n_PC) most reliable positions, the highest reliability on tie
+            let &wm_pos = info_pos[num_pc..].iter().rev().min_by_key(|&&i| i.count_ones()).unwrap();
+            bit_type[wm_pos] = PolarBitType::ParityCheck;
+        }
+
+        PolarCode {
+            k,
+            e,
+            n,
+            bit_type,
+            rate_match,
+            sub_block_interleaver,
+            channel_interleaver: PolarCode::channel_interleaver_pattern(e),
+        }
+    }
+
+    // 38.212, 5.4.1.3, triangular interleaver, written row by row and read column by column
+    fn channel_interleaver_pattern(e: usize) -> Vec<usize> {
+        let t = (1..).find(|t| t * (t + 1) / 2 >= e).unwrap();
+        let row_start = |i: usize| i * t - i * (i.max(1) - 1) / 2;
+        (0..t).flat_map(|j| (0..t - j).map(move |i| row_start(i) + j)).filter(|&k| k < e).collect()
+    }
+
+    // d = u * G_N, in place
+    fn polar_transform(bits: &mut [u8]) {
+        let mut step = 1;
+        while step < bits.len() {
+            for i in (0..bits.len()).step_by(2 * step) {
+                for j in i..i + step {
+                    bits[j] ^= bits[j + step];
+                }
+            }
+            step *= 2;
+        }
+    }
+
+    // c: K bits with CRC attached, return E rate matched and interleaved bits
+    pub fn encode(&self, c: &[u8]) -> Vec<u8> {
+        assert_eq!(c.len(), self.k, "polar code expects {} bits!", self.k);
+
+        // 38.212, 5.3.1.2
+        let mut info_bits = c.iter();
+        let mut pc_reg = [0u8; 5];
+        let mut d = self
+            .bit_type
+            .iter()
+            .map(|bit_type| {
+                pc_reg.rotate_left(1);
+                let bit = match bit_type {
+                    PolarBitType::Frozen => 0,
+                    PolarBitType::Info => *info_bits.next().unwrap(),
+                    PolarBitType::ParityCheck => pc_reg[0],
+                };
+                pc_reg[0] ^= bit;
+                bit
+            })
+            .collect::<Vec<_>>();
+        PolarCode::polar_transform(&mut d);
+
+        // 38.212, 5.4.1.1 and 5.4.1.2
+        let big_n = d.len();
+        let y = self.sub_block_interleaver.iter().map(|&j| d[j]).collect::<Vec<_>>();
+        let e = match self.rate_match {
+            PolarRateMatch::Repetition => (0..self.e).map(|k| y[k % big_n]).collect::<Vec<_>>(),
+            PolarRateMatch::Puncturing => y[big_n - self.e..].to_vec(),
+            PolarRateMatch::Shortening => y[..self.e].to_vec(),
+        };
+
+        self.channel_interleaver.iter().map(|&k| e[k]).collect()
+    }
+
+    // llr: E soft bits, positive for bit 0, list_size 1 is SC decoding
+    pub fn decode(&self, llr: &[f32], list_size: usize, crc_type: CrcType) -> PolarDecoded {
+        assert_eq!(llr.len(), self.e, "polar code expects {} LLRs!", self.e);
+        assert!(list_size > 0, "invalid list size {}!", list_size);
+
+        let mut e_llr = vec![0f32; self.e];
+        self.channel_interleaver.iter().zip(llr).for_each(|(&k, &l)| e_llr[k] = l);
+
+        // repetition: combine the LLRs, puncturing: unknown bits, shortening: known 0 bits
+        let big_n = 1 << self.n;
+        let mut y_llr = vec![0f32; big_n];
+        match self.rate_match {
+            PolarRateMatch::Repetition => e_llr.iter().enumerate().for_each(|(k, &l)| y_llr[k % big_n] += l),
+            PolarRateMatch::Puncturing => y_llr[big_n - self.e..].copy_from_slice(&e_llr),
+            PolarRateMatch::Shortening => {
+                y_llr[..self.e].copy_from_slice(&e_llr);
+                y_llr[self.e..].fill(SHORTENED_LLR);
+            }
+        }
+
+        let mut d_llr = vec![0f32; big_n];
+        self.sub_block_interleaver.iter().zip(&y_llr).for_each(|(&j, &l)| d_llr[j] = l);
+
+        // CRC aided selection, the paths are sorted by metric
+        let crc_len = crc_type.crc_len() as usize;
+        let candidates = self.scl_decode(&d_llr, list_size).iter().map(|path| self.info_bits(&path.u)).collect::<Vec<_>>();
+        let (bits, crc_pass) = match candidates.iter().find(|&c| crc_check(c, crc_type)) {
+            Some(c) => (c, true),
+            None => (&candidates[0], false),
+        };
+        PolarDecoded { bits: bits[..self.k - crc_len].to_vec(), crc_pass }
+    }
+
+    fn info_bits(&self, u: &[u8]) -> Vec<u8> {
+        u.iter().zip(&self.bit_type).filter(|&(_, &bit_type)| bit_type == PolarBitType::Info).map(|(&bit, _)| bit).collect()
+    }
+
+    fn scl_decode(&self, d_llr: &[f32], list_size: usize) -> Vec<ScPath> {
+        let mut paths = vec![ScPath::new(d_llr, self.n)];
+        for (i, bit_type) in self.bit_type.iter().enumerate() {
+            paths.iter_mut().for_each(|path| {
+                path.calc_llr(self.n, i);
+                path.pc_reg.rotate_left(1);
+            });
+            match bit_type {
+                PolarBitType::Frozen => paths.iter_mut().for_each(|path| path.decide(self.n, i, 0)),
+                PolarBitType::ParityCheck => paths.iter_mut().for_each(|path| {
+                    let bit = path.pc_reg[0];
+                    path.decide(self.n, i, bit)
+                }),
+                PolarBitType::Info => {
+                    let mut candidates = paths
+                        .iter()
+                        .enumerate()
+                        .flat_map(|(l, path)| [0u8, 1].map(|bit| (l, bit, path.metric_after(self.n, bit))))
+                        .collect::<Vec<_>>();
+                    candidates.sort_by(|a, b| a.2.total_cmp(&b.2));
+                    candidates.truncate(list_size);
+                    paths = candidates
+                        .iter()
+                        .map(|&(l, bit, _)| {
+                            let mut path = paths[l].clone();
+                            path.decide(self.n, i, bit);
+                            path
+                        })
+                        .collect();
+                }
+            }
+        }
+        paths.sort_by(|a, b| a.metric.total_cmp(&b.metric));
+        paths
+    }
+}
+
+impl ScPath {
+    fn new(d_llr: &[f32], n: usize) -> Self {
+        ScPath {
+            llr: (0..=n).map(|lambda| if lambda == 0 { d_llr.to_vec() } else { vec![0f32; 1 << (n - lambda)] }).collect(),
+            partial_sum: (0..=n).map(|lambda| vec![[0u8; 2]; 1 << (n - lambda)]).collect(),
+            u: Vec::with_capacity(1 << n),
+            pc_reg: [0u8; 5],
+            metric: 0f32,
+        }
+    }
+
+    // node of layer lambda for bit phi, the f/g combines entries beta and beta + half of layer lambda - 1
+    fn calc_llr(&mut self, lambda: usize, phi: usize) {
+        if lambda == 0 {
+            return;
+        }
+        if phi & 1 == 0 {
+            self.calc_llr(lambda - 1, phi >> 1);
+        }
+
+        let (prev, cur) = self.llr.split_at_mut(lambda);
+        let (prev, cur) = (&prev[lambda - 1], &mut cur[0]);
+        let half = cur.len();
+        for beta in 0..half {
+            let (a, b) = (prev[beta], prev[beta + half]);
+            cur[beta] = if phi & 1 == 0 {
+                a.signum() * b.signum() * a.abs().min(b.abs())
+            } else if self.partial_sum[lambda][beta][0] == 0 {
+                b + a
+            } else {
+                b - a
+            };
+        }
+    }
+
+    // phi is odd
+    fn update_partial_sum(&mut self, lambda: usize, phi: usize) {
+        let psi = phi >> 1;
+        let (prev, cur) = self.partial_sum.split_at_mut(lambda);
+        let (prev, cur) = (&mut prev[lambda - 1], &cur[0]);
+        let half = cur.len();
+        for beta in 0..half {
+            let [left, right] = cur[beta];
+            prev[beta][psi % 2] = left ^ right;
+            prev[beta + half][psi % 2] = right;
+        }
+        if psi % 2 == 1 {
+            self.update_partial_sum(lambda - 1, psi);
+        }
+    }
+
+    fn metric_after(&self, n: usize, bit: u8) -> f32 {
+        let llr = self.llr[n][0];
+        if (llr < 0f32) == (bit == 1) {
+            self.metric
+        } else {
+            self.metric + llr.abs()
+        }
+    }
+
+    fn decide(&mut self, n: usize, phi: usize, bit: u8) {
+        self.metric = self.metric_after(n, bit);
+        self.u.push(bit);
+        self.pc_reg[0] ^= bit;
+        self.partial_sum[n][0][phi % 2] = bit;
+        if phi % 2 == 1 {
+            self.update_partial_sum(n, phi);
+        }
+    }
+}
+
+impl UciPolar {
+    pub fn new(a: u32, e_uci: u32) -> Self {
+        assert!(a >= 12, "UCI of {} bits is not polar coded!", a);
+        // 38.212, 6.3.1.2.1, code block segmentation and CRC attachment
+        let num_cb = if (a >= 360 && e_uci >= 1088) || a >= 1013 { 2 } else { 1 };
+        let crc_type = if a <= 19 { CrcType::Crc6 } else { CrcType::Crc11 };
+        let a_prime = ceil(a, num_cb) * num_cb;
+        let k = a_prime / num_cb + crc_type.crc_len();
+        // 38.212, 6.3.1.4.1, E_r = floor(E_UCI / C_UCI)
+        let e = e_uci / num_cb;
+
+        UciPolar { a: a as usize, a_prime: a_prime as usize, num_cb: num_cb as usize, crc_type, code: PolarCode::new(k, e) }
+    }
+
+    pub fn encode(&self, a: &[u8]) -> Vec<u8> {
+        assert_eq!(a.len(), self.a, "UCI polar expects {} bits!", self.a);
+        // a zero is prepended if A is not a multiple of the number of code blocks
+        let mut a_prime = vec![0u8; self.a_prime - self.a];
+        a_prime.extend_from_slice(a);
+        a_prime
+            .chunks(self.a_prime / self.num_cb)
+            .flat_map(|segment| self.code.encode(&crc_attach(segment, self.crc_type)))
+            .collect()
+    }
+
+    pub fn decode(&self, llr: &[f32], list_size: usize) -> PolarDecoded {
+        assert!(llr.len() >= self.num_cb * self.code.e, "UCI polar expects {} LLRs!", self.num_cb * self.code.e);
+        let code_blocks = llr
+            .chunks_exact(self.code.e)
+            .take(self.num_cb)
+            .map(|cb_llr| self.code.decode(cb_llr, list_size, self.crc_type))
+            .collect::<Vec<_>>();
+
+        PolarDecoded {
+            bits: code_blocks.iter().flat_map(|cb| cb.bits.iter().copied()).skip(self.a_prime - self.a).collect(),
+            crc_pass: code_blocks.iter().all(|cb| cb.crc_pass),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::bits_to_llr;
+
+    fn test_bits(len: usize) -> Vec<u8> {
+        (0..len).map(|i| ((i * 7 + i / 3) % 5 % 2) as u8).collect()
+    }
+
+    #[test]
+    fn uci_polar_loopback() {
+        // (A, E_UCI) covering PC bits, repetition, puncturing, shortening and segmentation
+        for &(a, e_uci) in &[(12, 64), (19, 480), (20, 1200), (40, 64), (60, 96), (100, 576), (400, 1200)] {
+            let uci_polar = UciPolar::new(a, e_uci);
+            let bits = test_bits(a as usize);
+            let f = uci_polar.encode(&bits);
+            for list_size in [1, 8] {
+                let decoded = uci_polar.decode(&bits_to_llr(&f), list_size);
+                assert!(decoded.crc_pass, "A {}, E {}, L {}", a, e_uci, list_size);
+                assert_eq!(decoded.bits, bits, "A {}, E {}, L {}", a, e_uci, list_size);
+            }
+        }
+    }
+
+    #[test]
+    fn uci_polar_segmentation() {
+        // A >= 1013 is segmented whatever E_UCI
+        for &(a, e_uci, num_cb) in &[(400, 1000, 1), (400, 1088, 2), (1013, 2100, 2)] {
+            let uci_polar = UciPolar::new(a, e_uci);
+            assert_eq!(uci_polar.num_cb, num_cb, "A {}, E {}", a, e_uci);
+            let bits = test_bits(a as usize);
+            let decoded = uci_polar.decode(&bits_to_llr(&uci_polar.encode(&bits)), 1);
+            assert!(decoded.crc_pass && decoded.bits == bits, "A {}, E {}", a, e_uci);
+        }
+    }
+
+    #[test]
+    fn uci_polar_crc_fail() {
+        let uci_polar = UciPolar::new(30, 128);
+        let mut llr = bits_to_llr(&uci_polar.encode(&test_bits(30)));
+        llr.iter_mut().take(40).for_each(|l| *l = -*l);
+        assert!(!uci_polar.decode(&llr, 4).crc_pass);
+    }
+}
@@ -1,14 +1,19 @@
+use crate::bwp::*;
 use crate::constants::*;
 use crate::math::*;
+use crate::numerology::*;
+use crate::occupancy::PucchOccupancy;
 use crate::rrc_pucch::*;
 use core::{fmt, panic};
 use serde_derive::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PucchConfig {
+    pub bwp: Bwp,
+    pub pucch_group_seq_hopping: PucchGroupSeqHopping,
     pucch_resource_set: Vec<PucchResourceSet>,
     pucch_resource: Vec<PucchResource>,
-    pucch_formats: PucchFormatsConfig,
+    pub pucch_formats: PucchFormatsConfig,
     sr_resource: Option<Vec<SrResourceConfig>>,
     multi_csi_resource: Option<Vec<PucchResourceId>>,
     dl_data_to_ul_ack: Option<Vec<u32>>, // K1
@@ -36,22 +41,22 @@ pub struct PucchResourceId {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct PucchResource {
+pub struct PucchResource {
     pucch_resource_id: u32,
-    start_prb: u32,
-    intra_slot_freq_hopping: IntraSlotFreqHopping,
-    format: PucchFormat,
+    pub start_prb: u32,
+    pub intra_slot_freq_hopping: IntraSlotFreqHopping,
+    pub format: PucchFormat,
     format_type: PucchFormatType,
     max_hold_bits: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct PucchFormatConfig {
-    inter_slot_freq_hopping: bool,
-    addition_dmrs: bool,
+pub struct PucchFormatConfig {
+    pub inter_slot_freq_hopping: bool,
+    pub addition_dmrs: bool,
     max_coderate_x100: u32, // * 100
-    num_slots: u32,
-    pi2_bpsk: bool,
+    pub num_slots: u32,
+    pub pi2_bpsk: bool,
     simul_harq_csi: bool,
 }
 
@@ -154,7 +159,20 @@ impl From<PucchConfigR> for PucchConfig {
 
         let multi_csi_resource = PucchConfig::from_multi_csi_resource(&pucch_resource, &pucch_rrc.multi_csi_resource);
 
+        let bwp = match &pucch_rrc.bwp {
+            Some(bwp) => bwp.into(),
+            None => Bwp::default(),
+        };
+        pucch_resource.iter().for_each(|resource| resource.check(&bwp));
+
+        let pucch_group_seq_hopping = match &pucch_rrc.pucch_config_common {
+            Some(pucch_config_common) => pucch_config_common.pucch_group_seq_hopping,
+            None => PucchGroupSeqHopping::Neither,
+        };
+
         PucchConfig {
+            bwp,
+            pucch_group_seq_hopping,
             pucch_resource_set,
             pucch_resource,
             pucch_formats,
@@ -182,7 +200,7 @@ impl PucchResourceId {
             .unwrap_or_else(|| panic!("pucch resource id {} not found!", resource_id))
     }
 
-    fn pucch_resource<'a>(&'a self, pucch_config: &'a PucchConfig) -> &'a PucchResource {
+    pub fn pucch_resource<'a>(&self, pucch_config: &'a PucchConfig) -> &'a PucchResource {
         &pucch_config.pucch_resource[self.idx]
     }
 }
@@ -197,7 +215,7 @@ impl PucchConfig {
                 assert!(resources.len() <= 2, "Invalid multi_csi_resource {:?}!", resources);
                 let mut pucch_resource_id = resources
                     .iter()
-                    .map(|&id| PucchResourceId::from_pucch_resource_id(&pucch_resource, id))
+                    .map(|&id| PucchResourceId::from_pucch_resource_id(pucch_resource, id))
                     .collect::<Vec<_>>();
                 // sort the pucch resource from small capacity to large capacity
                 pucch_resource_id.sort_by_key(|resource_id| pucch_resource[resource_id.idx].max_hold_bits);
@@ -209,7 +227,7 @@ impl PucchConfig {
 }
 
 impl PucchResource {
-    fn pucch_format_config<'a>(&'a self, pucch_formats: &'a PucchFormatsConfig) -> &'a PucchFormatConfig {
+    pub fn pucch_format_config<'a>(&self, pucch_formats: &'a PucchFormatsConfig) -> &'a PucchFormatConfig {
         match self.format {
             PucchFormat::Format1 { .. } => &pucch_formats.pucch_format1,
             PucchFormat::Format2 { .. } => &pucch_formats.pucch_format2,
@@ -224,7 +242,7 @@ impl PucchResource {
     }
 
     // 38.211, Table 6.4.1.3.3.2-1, DMRS for PUCCH format 3 and 4
-    fn pucch_dmrs_pos(&self, pucch_formats: &PucchFormatsConfig) -> &[u32] {
+    pub fn pucch_dmrs_pos(&self, pucch_formats: &PucchFormatsConfig) -> &[u32] {
         let num_sym = match &self.format {
             PucchFormat::Format3 { num_rb: _, num_sym, .. } => *num_sym,
             PucchFormat::Format4 { num_sym, .. } => *num_sym,
@@ -232,27 +250,81 @@ impl PucchResource {
         };
 
         if num_sym == 4 {
-            match self.intra_slot_freq_hopping {
-                IntraSlotFreqHopping::Hopping { .. } => &[0, 2],
-                IntraSlotFreqHopping::NoHopping => &[1],
+            if self.is_intra_slot_hopping(pucch_formats) {
+                &[0, 2]
+            } else {
+                &[1]
             }
         } else {
             const START_SYM: u32 = 5;
-            const NUM_TABLE: usize = (NUM_SYM_PER_SLOT - START_SYM + 1) as usize;
+            const NUM_TABLE: usize = (NUM_SYM_PER_SLOT_NORMAL_CP - START_SYM + 1) as usize;
             let pucch_format_config = self.pucch_format_config(pucch_formats);
             let idx: usize = (num_sym - START_SYM) as usize;
             if pucch_format_config.addition_dmrs {
                 #[rustfmt::skip]
-                const PUCCH_DMRS_POS_TABLE: [&'static [u32]; NUM_TABLE] = [&[0, 3], &[1, 4], &[1, 4], &[1, 5], &[1, 6], &[1, 3, 6, 8], &[1, 3, 6, 9], &[1, 4, 7, 10], &[1, 4, 7, 11], &[1, 5, 8, 12]];
+                const PUCCH_DMRS_POS_TABLE: [&[u32]; NUM_TABLE] = [&[0, 3], &[1, 4], &[1, 4], &[1, 5], &[1, 6], &[1, 3, 6, 8], &[1, 3, 6, 9], &[1, 4, 7, 10], &[1, 4, 7, 11], &[1, 5, 8, 12]];
                 PUCCH_DMRS_POS_TABLE[idx]
             } else {
                 #[rustfmt::skip]
-                const PUCCH_DMRS_POS_TABLE: [&'static [u32]; NUM_TABLE] = [&[0, 3], &[1, 4], &[1, 4], &[1, 5], &[1, 6], &[2, 7], &[2, 7], &[2, 8], &[2, 9], &[3, 10]];
+                const PUCCH_DMRS_POS_TABLE: [&[u32]; NUM_TABLE] = [&[0, 3], &[1, 4], &[1, 4], &[1, 5], &[1, 6], &[2, 7], &[2, 7], &[2, 8], &[2, 9], &[3, 10]];
                 PUCCH_DMRS_POS_TABLE[idx]
             }
         }
     }
 
+    // 38.212, Table 6.3.1.4-1, total rate matching output sequence length
+    pub fn e_tot(&self, pucch_formats: &PucchFormatsConfig) -> u32 {
+        let pucch_format_config = self.pucch_format_config(pucch_formats);
+        match &self.format {
+            PucchFormat::Format2 { num_rb, num_sym, .. } => {
+                let data_sc = NUM_SC_PER_RB - 4;
+                let qm = QPSK_BITS;
+                (*num_rb) * data_sc * (*num_sym) * qm
+            }
+            PucchFormat::Format3 { num_rb, num_sym, .. } => {
+                let data_sc = NUM_SC_PER_RB;
+                let num_data_sym = *num_sym - self.pucch_num_dmrs_sym(pucch_formats);
+                let qm = if pucch_format_config.pi2_bpsk { BPSK_BITS } else { QPSK_BITS };
+                (*num_rb) * data_sc * num_data_sym * qm
+            }
+            PucchFormat::Format4 { num_sym, occ_len, .. } => {
+                let data_sc = NUM_SC_PER_RB / *occ_len;
+                let num_data_sym = *num_sym - self.pucch_num_dmrs_sym(pucch_formats);
+                let qm = if pucch_format_config.pi2_bpsk { BPSK_BITS } else { QPSK_BITS };
+                data_sc * num_data_sym * qm
+            }
+            _ => panic!("impossible to be here!"),
+        }
+    }
+
+    // the symbols are within the N_symb^slot symbols of the slot, so are the symbol bitmaps,
+    // and the PRBs of both hops are within the BWP
+    fn check(&self, bwp: &Bwp) {
+        let (start_sym, num_sym) = self.occupied_sym();
+        let num_sym_per_slot = bwp.numerology.num_sym_per_slot();
+        assert!(
+            start_sym + num_sym <= num_sym_per_slot,
+            "pucch resource {} exceeds the slot of {} symbols!",
+            self.pucch_resource_id,
+            num_sym_per_slot
+        );
+
+        let mut hop_start_prb = vec![self.start_prb];
+        if let IntraSlotFreqHopping::Hopping { second_prb } = self.intra_slot_freq_hopping {
+            hop_start_prb.push(second_prb);
+        }
+        for start_prb in hop_start_prb {
+            assert!(
+                bwp.contains(start_prb, self.num_rb()),
+                "pucch resource {} at PRB {} with {} RBs exceeds the BWP of {} RBs!",
+                self.pucch_resource_id,
+                start_prb,
+                self.num_rb(),
+                bwp.num_rb
+            );
+        }
+    }
+
     // 38.213, 9.2.5.2
     fn max_hold_bits(&self, pucch_formats: &PucchFormatsConfig) -> u32 {
         match &self.format {
@@ -260,28 +332,7 @@ impl PucchResource {
             PucchFormat::Format1 { .. } => 2,
             _ => {
                 let pucch_format_config = self.pucch_format_config(pucch_formats);
-                let num_bits = match &self.format {
-                    PucchFormat::Format2 { num_rb, num_sym, .. } => {
-                        let data_sc = NUM_SC_PER_RB - 4;
-                        let qm = QPSK_BITS;
-                        pucch_format_config.max_coderate_x100 * (*num_rb) * data_sc * (*num_sym) * qm
-                    }
-                    PucchFormat::Format3 { num_rb, num_sym, .. } => {
-                        let data_sc = NUM_SC_PER_RB;
-                        let num_data_sym = *num_sym - self.pucch_num_dmrs_sym(pucch_formats);
-                        let qm = if pucch_format_config.pi2_bpsk { BPSK_BITS } else { QPSK_BITS };
-                        pucch_format_config.max_coderate_x100 * (*num_rb) * data_sc * num_data_sym * qm
-                    }
-                    PucchFormat::Format4 { num_sym, occ_len, .. } => {
-                        let data_sc = NUM_SC_PER_RB / *occ_len;
-                        let num_data_sym = *num_sym - self.pucch_num_dmrs_sym(pucch_formats);
-                        let qm = if pucch_format_config.pi2_bpsk { BPSK_BITS } else { QPSK_BITS };
-                        pucch_format_config.max_coderate_x100 * data_sc * num_data_sym * qm
-                    }
-                    _ => panic!("impossible to be here!"),
-                };
-
-                num_bits / 100
+                pucch_format_config.max_coderate_x100 * self.e_tot(pucch_formats) / 100
             }
         }
     }
@@ -290,10 +341,12 @@ impl PucchResource {
 /******************** impl runtime **********************/
 impl PucchConfig {
     // 38.213, 9.2.1
+    #[allow(dead_code)]
     fn pucch_resource_set_for_uci(&self, o_uci: u32) -> &PucchResourceSet {
         self.pucch_resource_set.iter().find(|&set| o_uci <= set.max_payload_minus_1).unwrap()
     }
 
+    #[allow(dead_code)]
     fn pucch_resource_for_uci(
         &self,
         o_uci: u32,
@@ -329,8 +382,15 @@ impl PucchConfig {
 }
 
 impl PucchResource {
+    pub fn num_rb(&self) -> u32 {
+        match self.format {
+            PucchFormat::Format2 { num_rb, .. } | PucchFormat::Format3 { num_rb, .. } => num_rb,
+            _ => 1,
+        }
+    }
+
     // (start_sym, num_sym)
-    fn occupied_sym(&self) -> (u32, u32) {
+    pub fn occupied_sym(&self) -> (u32, u32) {
         match self.format {
             PucchFormat::Format0 { init_cyclic_shift: _, num_sym, start_sym } => (start_sym, num_sym),
             PucchFormat::Format1 { init_cyclic_shift: _, num_sym, start_sym, .. } => (start_sym, num_sym),
@@ -340,13 +400,59 @@ impl PucchResource {
         }
     }
 
-    fn sym_bitmap(&self) -> u32 {
-        let (start_sym, num_sym) = self.occupied_sym();
-        mask(start_sym, num_sym)
+    // 38.213, 9.2.6, hopping between the slots of a multi-slot PUCCH, the second hop PRB is from intra_slot_freq_hopping
+    // and the intra slot hopping is not applied
+    pub fn is_inter_slot_hopping(&self, pucch_formats: &PucchFormatsConfig) -> bool {
+        match (&self.format, self.intra_slot_freq_hopping) {
+            (PucchFormat::Format0 { .. } | PucchFormat::Format2 { .. }, _) => false,
+            (_, IntraSlotFreqHopping::NoHopping) => false,
+            _ => {
+                let pucch_format_config = self.pucch_format_config(pucch_formats);
+                pucch_format_config.inter_slot_freq_hopping && pucch_format_config.num_slots > 1
+            }
+        }
     }
 
-    fn is_overlap(&self, pucch_resource: &PucchResource) -> bool {
-        (self.sym_bitmap() & pucch_resource.sym_bitmap()) != 0
+    pub fn is_intra_slot_hopping(&self, pucch_formats: &PucchFormatsConfig) -> bool {
+        let is_hopping = matches!(self.intra_slot_freq_hopping, IntraSlotFreqHopping::Hopping { .. });
+        is_hopping && !self.is_inter_slot_hopping(pucch_formats)
+    }
+
+    // 38.211, 6.3.2.1, n_hop of symbol l counted from the first PUCCH symbol, the first hop has floor(num_sym / 2) symbols
+    pub fn n_hop(&self, pucch_formats: &PucchFormatsConfig, l: u32) -> u32 {
+        let (_, num_sym) = self.occupied_sym();
+        if self.is_intra_slot_hopping(pucch_formats) && l >= floor(num_sym, 2) {
+            1
+        } else {
+            0
+        }
+    }
+
+    pub fn hop_start_prb(&self, n_hop: u32) -> u32 {
+        match (self.intra_slot_freq_hopping, n_hop) {
+            (IntraSlotFreqHopping::Hopping { second_prb }, 1) => second_prb,
+            _ => self.start_prb,
+        }
+    }
+
+    // the first PRB of symbol l in the slot_idx-th slot of the PUCCH transmission
+    // 38.213, 9.2.6, with inter slot hopping, the slots with even number use start_prb and odd number use second_prb
+    pub fn sym_start_prb(&self, pucch_formats: &PucchFormatsConfig, l: u32, slot_idx: u32) -> u32 {
+        if self.is_inter_slot_hopping(pucch_formats) {
+            self.hop_start_prb(slot_idx % 2)
+        } else {
+            self.hop_start_prb(self.n_hop(pucch_formats, l))
+        }
+    }
+
+    // time-frequency occupancy in the first slot, the code of format 0 is not needed for the overlap of the same UE
+    fn occupancy(&self, pucch_config: &PucchConfig) -> PucchOccupancy {
+        PucchOccupancy::new(pucch_config, self, 0, 0, false)
+    }
+
+    // the PUCCH of the same UE, the collision of the PUCCH of different UEs is PucchOccupancy::is_collision
+    fn is_overlap(&self, pucch_config: &PucchConfig, pucch_resource: &PucchResource) -> bool {
+        self.occupancy(pucch_config).is_time_overlap(&pucch_resource.occupancy(pucch_config))
     }
 
     fn pucch_format_type(format: &PucchFormat) -> PucchFormatType {
@@ -357,11 +463,11 @@ impl PucchResource {
     }
 
     // the smaller, the higher priority
-    fn q_set_priority(&self) -> u32 {
+    fn q_set_priority(&self, num_sym_per_slot: u32) -> u32 {
         let (start_sym, num_sym) = self.occupied_sym();
         // the smaller start_sym, the higher priority
         // if start_sym is the same, the large num_sym, the higher priority
-        (start_sym + 1) * NUM_SYM_PER_SLOT - num_sym
+        (start_sym + 1) * num_sym_per_slot - num_sym
     }
 }
 
@@ -385,6 +491,7 @@ pub struct SrRequest {
     pub sr_id: u32,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, PartialEq, Clone)]
 pub enum PucchChannelType {
     HarqDci,
@@ -398,6 +505,7 @@ pub enum PucchChannelType {
     HarqCsiSrMulti,
 }
 
+#[allow(dead_code)]
 impl PucchChannelType {
     check_func!(is_csi, PucchChannelType::Csi { .. });
     check_func!(is_sr, PucchChannelType::Sr { .. });
@@ -445,7 +553,7 @@ impl PucchLogicChannel {
     {
         pucch_channels
             .scan(0u32, |accum_bitmap, channel| {
-                let pucch_bitmap = channel.pucch_resource(pucch_config).sym_bitmap();
+                let pucch_bitmap = channel.pucch_resource(pucch_config).occupancy(pucch_config).sym_bitmap();
                 let is_overlap = (*accum_bitmap & pucch_bitmap) != 0;
                 *accum_bitmap |= pucch_bitmap;
                 Some(is_overlap)
@@ -457,12 +565,10 @@ impl PucchLogicChannel {
     where
         T: Iterator<Item = &'a PucchLogicChannel>,
     {
-        let pucch_channels_bitmap = pucch_channels.fold(0u32, |accum_bitmap, channel| {
-            let pucch_bitmap = channel.pucch_resource(pucch_config).sym_bitmap();
-            accum_bitmap | pucch_bitmap
-        });
-
-        (self.pucch_resource(pucch_config).sym_bitmap() & pucch_channels_bitmap) != 0
+        let occupancy = self.pucch_resource(pucch_config).occupancy(pucch_config);
+        pucch_channels
+            .map(|channel| channel.pucch_resource(pucch_config).occupancy(pucch_config))
+            .any(|channel_occupancy| occupancy.is_time_overlap(&channel_occupancy))
     }
 }
 
@@ -485,7 +591,7 @@ pub fn pucch_proc(pucch_config: &PucchConfig, pucch_logic_channel: &mut Vec<Pucc
     q_set_proc(pucch_config, pucch_logic_channel);
 }
 
-fn dci_harq_pucch_proc(pucch_config: &PucchConfig, pucch_logic_channel: &mut Vec<PucchLogicChannel>) {
+fn dci_harq_pucch_proc(_pucch_config: &PucchConfig, pucch_logic_channel: &mut Vec<PucchLogicChannel>) {
     let dci_harq_exist = pucch_logic_channel.iter().find(|&channel| channel.channel_type.is_dci_harq()).is_some();
     if dci_harq_exist {
         swap_remove_filter(pucch_logic_channel, |channel| channel.channel_type.is_sps_harq());
@@ -497,7 +603,7 @@ fn csi_pucch_proc(pucch_config: &PucchConfig, pucch_logic_channel: &mut Vec<Pucc
 
     if csi_pucch_idx.len() > 1 {
         match &pucch_config.multi_csi_resource {
-            Some(mult_csi_resources) => {
+            Some(_) => {
                 let csi_pucch = csi_pucch_idx.iter().map(|&i| &pucch_logic_channel[i]);
                 let is_overlap = PucchLogicChannel::is_overlap_among_channels(pucch_config, csi_pucch);
                 // if multi_csi_resources is configured, and there's overlap between csi PUCCH,
@@ -573,7 +679,7 @@ fn select_csi_pucch_proc(
                 let resource = pucch_logic_channel[idx].pucch_resource(pucch_config);
                 (idx != highest_priority_csi_channel_idx)
                     && resource.format_type == PucchFormatType::ShortPucch
-                    && !resource.is_overlap(highest_pucch_resource)
+                    && !resource.is_overlap(pucch_config, highest_pucch_resource)
             })
             .min_by_key(|&&idx| {
                 let csi_report = into_variant!(pucch_logic_channel[idx].channel_type, PucchChannelType::Csi);
@@ -583,7 +689,7 @@ fn select_csi_pucch_proc(
             .iter()
             .filter(|&&idx| {
                 let resource = pucch_logic_channel[idx].pucch_resource(pucch_config);
-                (idx != highest_priority_csi_channel_idx) && !resource.is_overlap(highest_pucch_resource)
+                (idx != highest_priority_csi_channel_idx) && !resource.is_overlap(pucch_config, highest_pucch_resource)
             })
             .min_by_key(|&&idx| {
                 let csi_report = into_variant!(pucch_logic_channel[idx].channel_type, PucchChannelType::Csi);
@@ -616,10 +722,11 @@ fn sr_pucch_proc(pucch_config: &PucchConfig, pucch_logic_channel: &mut Vec<Pucch
     });
 }
 
-fn harq_csi_simul_proc(pucch_config: &PucchConfig, pucch_logic_channel: &mut Vec<PucchLogicChannel>) {}
+fn harq_csi_simul_proc(_pucch_config: &PucchConfig, _pucch_logic_channel: &mut [PucchLogicChannel]) {}
 
 fn q_set_proc(pucch_config: &PucchConfig, pucch_logic_channel: &mut Vec<PucchLogicChannel>) {
-    pucch_logic_channel.sort_by_key(|channel| channel.pucch_resource(pucch_config).q_set_priority());
+    let num_sym_per_slot = pucch_config.bwp.numerology.num_sym_per_slot();
+    pucch_logic_channel.sort_by_key(|channel| channel.pucch_resource(pucch_config).q_set_priority(num_sym_per_slot));
 
     println!("after sort: {:?}", pucch_logic_channel);
 }
@@ -628,9 +735,19 @@ fn q_set_proc(pucch_config: &PucchConfig, pucch_logic_channel: &mut Vec<PucchLog
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::*;
 
     #[test]
     fn debug() {
         println!("test");
     }
+
+    #[test]
+    fn e_tot() {
+        let pucch_config = test_pucch_config(&[]);
+        let e_tot = |id| PucchResourceId::new(&pucch_config, id).pucch_resource(&pucch_config).e_tot(&pucch_config.pucch_formats);
+        // format 2: 2 PRB, 2 symbols; format 3: 10 PRB, 6 symbols with 2 DMRS, pi/2-BPSK
+        assert_eq!(e_tot(2), 64);
+        assert_eq!(e_tot(3), 480);
+    }
 }
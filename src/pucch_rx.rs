@@ -0,0 +1,733 @@
+use crate::combining::*;
+use crate::complex::*;
+use crate::constants::*;
+use crate::dft::*;
+use crate::gold::*;
+use crate::modulation::*;
+use crate::numerology::Numerology;
+use crate::pucch::*;
+use crate::pucch_tx::*;
+use crate::rrc_pucch::*;
+use crate::sequence::*;
+use std::f32::consts::{PI, SQRT_2};
+
+// 38.213, 9.2.3, result of the PUCCH format 0 detection
+#[derive(Debug, Clone, PartialEq)]
+pub struct Format0Decoded {
+    pub harq_ack: Vec<u8>, // empty for DTX or positive SR only
+    pub sr: bool,
+    pub metric: f32, // the correlation energy of the best hypothesis over the received energy, in [0, 1]
+    pub dtx: bool,
+    pub sinr_db: Vec<f32>, // per receive antenna, on the best hypothesis
+    pub measurement: PucchMeasurement,
+}
+
+// 38.213, 9.2.3, result of the PUCCH format 1 detection
+#[derive(Debug, Clone, PartialEq)]
+pub struct Format1Decoded {
+    pub llr: Vec<f32>, // LLR of the HARQ-ACK bits, positive for bit 0, empty for DTX
+    pub metric: f32,   // the despread energy over the received energy, in [0, 1]
+    pub dtx: bool,
+    pub sinr_db: Vec<f32>, // per receive antenna
+    pub measurement: PucchMeasurement,
+    pub sync: Option<SyncMeasurement>, // None for DTX
+}
+
+// result of the PUCCH format 2/3/4 demodulation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Format234Decoded {
+    pub llr: Vec<f32>,     // descrambled LLRs of the E_tot coded bits, positive for bit 0
+    pub sinr_db: Vec<f32>, // per receive antenna
+    pub measurement: PucchMeasurement,
+    pub sync: Option<SyncMeasurement>, // format 3/4 only
+}
+
+// the per antenna and the reported SINR are floored, the estimated signal power can be 0
+const MIN_SINR_DB: f32 = -30f32;
+
+// per PUCCH measurements from the DMRS, or from the sequence correlation for format 0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PucchMeasurement {
+    pub rsrp_db: f32,   // 10 * log10 of the received signal power per RE, averaged over the antennas
+    pub noise_var: f32, // per RE, averaged over the antennas
+    pub sinr_db: f32,
+    pub dtx_confidence: f32, // noise fraction of the reference signal energy, near 1 for DTX and near 0 for a strong PUCCH
+}
+
+// signal[a] and noise_var[a] of each antenna, the signal power is without the estimation noise
+// the per antenna SINR and the measurement averaged over the antennas
+fn pucch_measurement(signal: &[f32], noise_var: &[f32]) -> (Vec<f32>, PucchMeasurement) {
+    let per_antenna = signal.iter().zip(noise_var).map(|(&signal, &noise_var)| sinr_db(signal, noise_var).max(MIN_SINR_DB));
+    let num_rx = signal.len() as f32;
+    let (signal, noise_var) = (signal.iter().sum::<f32>() / num_rx, noise_var.iter().sum::<f32>() / num_rx);
+    let measurement = PucchMeasurement {
+        rsrp_db: 10f32 * signal.max(f32::MIN_POSITIVE).log10(),
+        noise_var,
+        sinr_db: sinr_db(signal, noise_var).max(MIN_SINR_DB),
+        dtx_confidence: noise_var / (signal + noise_var),
+    };
+    (per_antenna.collect(), measurement)
+}
+
+// timing and frequency offset measured on the DMRS
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyncMeasurement {
+    pub timing_offset_s: f32, // positive for a late arrival
+    pub cfo_hz: Option<f32>,  // None without 2 DMRS symbols in a hop
+}
+
+// LS estimates ls[a][k] = r_a(k) * conj(dmrs(k)) on the contiguous subcarriers of DMRS symbol l in hop n_hop
+struct DmrsLs {
+    l: u32,
+    n_hop: u32,
+    ls: Vec<Vec<Complex>>,
+}
+
+// the timing from the phase slope over adjacent subcarriers, the CFO from the phase rotation between the DMRS
+// symbols of the same hop, the unambiguous ranges are +-1 / (2 * SCS) and +-1 / (2 * DMRS distance)
+fn dmrs_sync(dmrs: &[DmrsLs], numerology: &Numerology) -> SyncMeasurement {
+    let slope = dmrs
+        .iter()
+        .flat_map(|dmrs| &dmrs.ls)
+        .map(|ls| ls.windows(2).map(|ls| ls[1] * ls[0].conj()).sum::<Complex>())
+        .sum::<Complex>();
+    let timing_offset_s = -slope.arg() / (2f32 * PI * numerology.scs_hz() as f32);
+
+    // per symbol phase of each pair, weighted by the correlation magnitude
+    let (mut phase, mut weight) = (0f32, 0f32);
+    for (a, b) in dmrs.iter().zip(dmrs.iter().skip(1)).filter(|(a, b)| a.n_hop == b.n_hop) {
+        let c =
+            a.ls.iter()
+                .zip(&b.ls)
+                .map(|(x, y)| y.iter().zip(x).map(|(&y, &x)| y * x.conj()).sum::<Complex>())
+                .sum::<Complex>();
+        phase += c.abs() * c.arg() / (b.l - a.l) as f32;
+        weight += c.abs();
+    }
+    let cfo_hz = (weight > 0f32).then(|| phase / weight / (2f32 * PI * numerology.sym_duration_s() as f32));
+    SyncMeasurement { timing_offset_s, cfo_hz }
+}
+
+// the received symbols of the resource on each antenna have the same layout
+fn check_rx(rx: &[Vec<PucchSymbol>], num_sym: u32) {
+    assert!(!rx.is_empty(), "no receive antenna!");
+    assert!(rx.iter().all(|rx| rx.len() as u32 == num_sym), "invalid PUCCH symbols!");
+}
+
+// sum_a,l |<r_a,l, s_l>|^2 / (sum_a,l ||r_a,l||^2 * ||s_l||^2), non-coherent over the symbols and the antennas
+fn seq_corr_metric(rx: &[Vec<PucchSymbol>], refs: &[Vec<Complex>]) -> f32 {
+    let (corr, energy) = rx.iter().flat_map(|rx| rx.iter().zip(refs)).fold((0f32, 0f32), |(corr, energy), (symbol, seq)| {
+        let c = symbol.data.iter().zip(seq).map(|(&r, &s)| r * s.conj()).sum::<Complex>();
+        let ref_energy = seq.iter().map(|s| s.norm_sqr()).sum::<f32>();
+        let rx_energy = symbol.data.iter().map(|r| r.norm_sqr()).sum::<f32>();
+        (corr + c.norm_sqr(), energy + rx_energy * ref_energy)
+    });
+    if energy > 0f32 {
+        corr / energy
+    } else {
+        0f32
+    }
+}
+
+// (|h|^2, noise_var), |h|^2 ~ (|c|^2 / N - noise_var) / N with c = <r_l, s_l>, the noise from the energy orthogonal to s_l
+fn seq_power(rx: &[PucchSymbol], refs: &[Vec<Complex>]) -> (f32, f32) {
+    let (mut signal, mut residual, mut num_dof) = (0f32, 0f32, 0);
+    for (symbol, seq) in rx.iter().zip(refs) {
+        let len = seq.len() as f32;
+        let c = symbol.data.iter().zip(seq).map(|(&r, &s)| r * s.conj()).sum::<Complex>();
+        signal += c.norm_sqr() / len;
+        residual += symbol.data.iter().map(|r| r.norm_sqr()).sum::<f32>() - c.norm_sqr() / len;
+        num_dof += seq.len() - 1;
+    }
+    let noise_var = (residual / num_dof as f32).max(f32::MIN_POSITIVE);
+    let len = refs.iter().map(|seq| seq.len()).sum::<usize>() as f32;
+    (((signal - rx.len() as f32 * noise_var) / len).max(0f32), noise_var)
+}
+
+// 38.213, 9.2.3 and 9.2.5.1, PUCCH format 0 detection, rx[a] are the received symbols of the resource on antenna a
+// o_harq HARQ-ACK bits are expected, with the positive SR hypotheses if sr is on an SR occasion
+// format 0 has no DMRS, the antennas are combined non-coherently
+pub fn pucch_format0_rx(
+    pucch_config: &PucchConfig,
+    pucch_resource: &PucchResource,
+    params: &PucchTxParams,
+    rx: &[Vec<PucchSymbol>],
+    o_harq: u32,
+    sr: bool,
+    dtx_threshold: f32,
+) -> Format0Decoded {
+    assert!(matches!(pucch_resource.format, PucchFormat::Format0 { .. }), "not PUCCH format 0!");
+    let (_, num_sym) = pucch_resource.occupied_sym();
+    check_rx(rx, num_sym);
+
+    let (harq_ack, sr, metric, refs) = format0_uci_candidates(o_harq, sr)
+        .into_iter()
+        .map(|(harq_ack, sr)| {
+            let m_cs = format0_m_cs(&harq_ack, sr);
+            let refs = (0..num_sym)
+                .map(|l| pucch_base_seq(pucch_config, pucch_resource, params, l, m_cs, NUM_SC_PER_RB as usize))
+                .collect::<Vec<_>>();
+            (harq_ack, sr, seq_corr_metric(rx, &refs), refs)
+        })
+        .max_by(|a, b| a.2.total_cmp(&b.2))
+        .expect("no PUCCH format 0 hypothesis!");
+    let (signal, noise_var): (Vec<_>, Vec<_>) = rx.iter().map(|rx| seq_power(rx, &refs)).unzip();
+    let (sinr_db, measurement) = pucch_measurement(&signal, &noise_var);
+
+    if metric < dtx_threshold {
+        Format0Decoded { harq_ack: vec![], sr: false, metric, dtx: true, sinr_db, measurement }
+    } else {
+        Format0Decoded { harq_ack, sr, metric, dtx: false, sinr_db, measurement }
+    }
+}
+
+// 38.211, 6.3.2.4 and 6.4.1.3.1, coherent PUCCH format 1 receiver of num_bits HARQ-ACK bits
+// per hop, the DMRS symbols estimate the channel and the UCI symbols are combined after the OCC despreading
+pub fn pucch_format1_rx(
+    pucch_config: &PucchConfig,
+    pucch_resource: &PucchResource,
+    params: &PucchTxParams,
+    rx: &[Vec<PucchSymbol>],
+    num_bits: usize,
+    combining: Combining,
+    dtx_threshold: f32,
+) -> Format1Decoded {
+    assert!(matches!(pucch_resource.format, PucchFormat::Format1 { .. }), "not PUCCH format 1!");
+    assert!(num_bits == 1 || num_bits == 2, "invalid PUCCH format 1 payload size {}!", num_bits);
+    let pucch_formats = &pucch_config.pucch_formats;
+    let (_, num_sym) = pucch_resource.occupied_sym();
+    check_rx(rx, num_sym);
+    let num_sc = NUM_SC_PER_RB as usize;
+
+    // z_a,l = <r_a,l, w(l) * r_u,v(n)> / 12, the channel on DMRS symbols and the channel times d(0) on UCI symbols
+    let refs = (0..num_sym)
+        .map(|l| {
+            let w = format1_time_occ(pucch_formats, pucch_resource, l);
+            pucch_base_seq(pucch_config, pucch_resource, params, l, 0, num_sc).iter().map(|&r| r * w).collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let z = rx
+        .iter()
+        .map(|rx| {
+            rx.iter()
+                .zip(&refs)
+                .map(|(symbol, seq)| {
+                    symbol.data.iter().zip(seq).map(|(&r, &s)| r * s.conj()).sum::<Complex>() / num_sc as f32
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // (n_hop, is_uci) groups of the symbols with the same channel and OCC
+    let group = |l: u32| (pucch_resource.n_hop(pucch_formats, l), l % 2 == 1);
+    let mut groups: Vec<((u32, bool), Vec<u32>)> = Vec::new();
+    for l in 0..num_sym {
+        match groups.iter_mut().find(|(key, _)| *key == group(l)) {
+            Some((_, syms)) => syms.push(l),
+            None => groups.push((group(l), vec![l])),
+        }
+    }
+    // the mean of each group on all the antennas
+    let group_mean = |syms: &[u32]| {
+        z.iter().map(|z| syms.iter().map(|&l| z[l as usize]).sum::<Complex>() / syms.len() as f32).collect::<Vec<_>>()
+    };
+
+    // spatial noise covariance from the residual after removing the estimate of each group
+    let mut cov = Covariance::new(rx.len());
+    let mut despread_energy = 0f32;
+    for (_, syms) in &groups {
+        let g = group_mean(syms);
+        for &l in syms {
+            for (k, &s) in refs[l as usize].iter().enumerate() {
+                let e = rx.iter().zip(&g).map(|(rx, &g)| rx[l as usize].data[k] - g * s).collect::<Vec<_>>();
+                cov.add(&e);
+            }
+        }
+        cov.add_estimate();
+        despread_energy += (num_sc * syms.len()) as f32 * g.iter().map(|g| g.norm_sqr()).sum::<f32>();
+    }
+    // the DMRS power of each antenna, the group means average 12 * N REs
+    let dmrs_groups = groups.iter().filter(|((_, is_uci), _)| !is_uci).map(|(_, syms)| syms).collect::<Vec<_>>();
+    let noise_var = (0..rx.len()).map(|a| cov.noise_var(a)).collect::<Vec<_>>();
+    let signal = dmrs_groups
+        .iter()
+        .map(|syms| {
+            let g = group_mean(syms);
+            g.iter().zip(&noise_var).map(|(g, &noise_var)| g.norm_sqr() - noise_var / (num_sc * syms.len()) as f32).collect()
+        })
+        .fold(vec![0f32; rx.len()], |sum: Vec<f32>, power: Vec<f32>| sum.iter().zip(power).map(|(s, p)| s + p).collect())
+        .iter()
+        .map(|&power| (power / dmrs_groups.len() as f32).max(0f32))
+        .collect::<Vec<_>>();
+    let (sinr_db, measurement) = pucch_measurement(&signal, &noise_var);
+
+    let rx_energy = rx.iter().flatten().flat_map(|symbol| symbol.data.iter()).map(|r| r.norm_sqr()).sum::<f32>();
+    let metric = if rx_energy > 0f32 { despread_energy / rx_energy } else { 0f32 };
+    if metric < dtx_threshold {
+        return Format1Decoded { llr: vec![], metric, dtx: true, sinr_db, measurement, sync: None };
+    }
+
+    // a = sum_hop 12 * N_uci * h^H * W * z_uci, the combined d(0) scaled by its SNR
+    let combiner = cov.combiner(combining);
+    let a = groups
+        .iter()
+        .filter(|((_, is_uci), _)| *is_uci)
+        .map(|((n_hop, _), syms)| {
+            let h = groups.iter().find(|(key, _)| *key == (*n_hop, false)).map(|(_, dmrs)| group_mean(dmrs));
+            let h = h.expect("no DMRS in the hop!");
+            let (x, g) = combiner.combine(&h, &group_mean(syms));
+            x * (g * (num_sc * syms.len()) as f32)
+        })
+        .sum::<Complex>();
+    let llr = match num_bits {
+        1 => vec![2f32 * SQRT_2 * (a.re + a.im)],
+        _ => vec![2f32 * SQRT_2 * a.re, 2f32 * SQRT_2 * a.im],
+    };
+    let dmrs_ls = (0..num_sym)
+        .step_by(2)
+        .map(|l| DmrsLs {
+            l,
+            n_hop: pucch_resource.n_hop(pucch_formats, l),
+            ls: rx
+                .iter()
+                .map(|rx| rx[l as usize].data.iter().zip(&refs[l as usize]).map(|(&r, &s)| r * s.conj()).collect())
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+    let sync = Some(dmrs_sync(&dmrs_ls, &pucch_config.bwp.numerology));
+    Format1Decoded { llr, metric, dtx: false, sinr_db, measurement, sync }
+}
+
+// 38.213, 9.2.4, on-off keying of the positive SR on an SR only PUCCH format 1 resource
+#[allow(dead_code)]
+pub fn pucch_format1_sr_rx(
+    pucch_config: &PucchConfig,
+    pucch_resource: &PucchResource,
+    params: &PucchTxParams,
+    rx: &[Vec<PucchSymbol>],
+    dtx_threshold: f32,
+) -> bool {
+    !pucch_format1_rx(pucch_config, pucch_resource, params, rx, 1, Combining::Mrc, dtx_threshold).dtx
+}
+
+// 38.211, 6.3.2.5.1 and 6.3.2.6.1, the LLRs of the scrambled bits are negated where c(i) = 1
+pub fn pucch_descrambling(llr: &[f32], params: &PucchTxParams) -> Vec<f32> {
+    let c = gold_seq(c_init_pucch_scrambling(params.n_rnti, params.n_id_scrambling()), 0, llr.len());
+    llr.iter().zip(&c).map(|(&llr, &c)| if c == 1 { -llr } else { llr }).collect()
+}
+
+// LS channel estimate of each antenna from the DMRS ls[a][i] = r_a(i) * conj(dmrs(i)), the residuals go to cov
+fn ls_channel_est(ls: &[Vec<Complex>], cov: &mut Covariance) -> Vec<Complex> {
+    let h = ls.iter().map(|ls| ls.iter().sum::<Complex>() / ls.len() as f32).collect::<Vec<_>>();
+    for i in 0..ls[0].len() {
+        cov.add(&ls.iter().zip(&h).map(|(ls, &h)| ls[i] - h).collect::<Vec<_>>());
+    }
+    cov.add_estimate();
+    h
+}
+
+// per antenna SINR and the measurement of the channel estimates h[..][a], each averages num_avg REs
+fn channel_measurement<'a>(
+    h: impl Iterator<Item = &'a Vec<Complex>>,
+    num_avg: usize,
+    cov: &Covariance,
+) -> (Vec<f32>, PucchMeasurement) {
+    let mut power = vec![0f32; cov.num_rx()];
+    let mut num_est = 0;
+    for h in h {
+        power.iter_mut().zip(h).for_each(|(power, h)| *power += h.norm_sqr());
+        num_est += 1;
+    }
+    let noise_var = (0..cov.num_rx()).map(|a| cov.noise_var(a)).collect::<Vec<_>>();
+    let signal = power
+        .iter()
+        .zip(&noise_var)
+        .map(|(&power, &noise_var)| (power / num_est.max(1) as f32 - noise_var / num_avg as f32).max(0f32))
+        .collect::<Vec<_>>();
+    pucch_measurement(&signal, &noise_var)
+}
+
+// 38.211, 6.3.2.5 and 6.4.1.3.2, PUCCH format 2 receiver, the descrambled LLRs of the E_tot coded bits
+// the channel is estimated per PRB of each symbol from its 4 DMRS
+pub fn pucch_format2_rx(
+    pucch_config: &PucchConfig,
+    pucch_resource: &PucchResource,
+    params: &PucchTxParams,
+    rx: &[Vec<PucchSymbol>],
+    combining: Combining,
+) -> Format234Decoded {
+    assert!(matches!(pucch_resource.format, PucchFormat::Format2 { .. }), "not PUCCH format 2!");
+    let num_rb = pucch_resource.num_rb() as usize;
+    let num_sc = NUM_SC_PER_RB as usize;
+    let num_sym_per_slot = pucch_config.bwp.numerology.num_sym_per_slot();
+    let (_, num_sym) = pucch_resource.occupied_sym();
+    check_rx(rx, num_sym);
+
+    // h[l][prb][a]
+    let mut cov = Covariance::new(rx.len());
+    let h = (0..num_sym as usize)
+        .map(|l| {
+            let symbol = &rx[0][l];
+            let m_start = 4 * pucch_config.bwp.prb_to_crb(symbol.start_prb) as usize;
+            let dmrs = format2_dmrs(params, num_sym_per_slot, symbol.sym, m_start, 4 * num_rb);
+            (0..num_rb)
+                .map(|prb| {
+                    let ls = rx
+                        .iter()
+                        .map(|rx| {
+                            let prb_data = &rx[l].data[prb * num_sc..(prb + 1) * num_sc];
+                            let dmrs_re = prb_data.iter().enumerate().filter(|&(k, _)| is_format2_dmrs_sc(k));
+                            dmrs_re.zip(&dmrs[4 * prb..4 * (prb + 1)]).map(|((_, &r), &d)| r * d.conj()).collect()
+                        })
+                        .collect::<Vec<Vec<_>>>();
+                    ls_channel_est(&ls, &mut cov)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let (sinr_db, measurement) = channel_measurement(h.iter().flatten(), 4, &cov);
+
+    let combiner = cov.combiner(combining);
+    let mut x = Vec::new();
+    let mut noise_var = Vec::new();
+    for (l, h) in h.iter().enumerate() {
+        for k in (0..num_rb * num_sc).filter(|&k| !is_format2_dmrs_sc(k % num_sc)) {
+            let (x_k, g) = combiner.combine(&h[k / num_sc], &rx.iter().map(|rx| rx[l].data[k]).collect::<Vec<_>>());
+            x.push(x_k);
+            noise_var.push(1f32 / g);
+        }
+    }
+    Format234Decoded { llr: pucch_descrambling(&qpsk_demodulate(&x, &noise_var), params), sinr_db, measurement, sync: None }
+}
+
+// 38.211, 6.3.2.6 and 6.4.1.3.3, PUCCH format 3/4 receiver, the descrambled LLRs of the E_tot coded bits
+// the channel is estimated per PRB of each hop from the DMRS symbols, then combining, IDFT and OCC removal
+pub fn pucch_format34_rx(
+    pucch_config: &PucchConfig,
+    pucch_resource: &PucchResource,
+    params: &PucchTxParams,
+    rx: &[Vec<PucchSymbol>],
+    combining: Combining,
+) -> Format234Decoded {
+    let (m_sc, n_sf) = format34_sc_sf(pucch_resource);
+    let pucch_formats = &pucch_config.pucch_formats;
+    let num_sc = NUM_SC_PER_RB as usize;
+    let dmrs_pos = pucch_resource.pucch_dmrs_pos(pucch_formats);
+    let (_, num_sym) = pucch_resource.occupied_sym();
+    check_rx(rx, num_sym);
+
+    // LS on the DMRS symbols in increasing order
+    let dmrs_ls = dmrs_pos
+        .iter()
+        .map(|&l| {
+            let dmrs = pucch_base_seq(pucch_config, pucch_resource, params, l, 0, m_sc);
+            let ls =
+                rx.iter().map(|rx| rx[l as usize].data.iter().zip(&dmrs).map(|(&r, d)| r * d.conj()).collect()).collect();
+            DmrsLs { l, n_hop: pucch_resource.n_hop(pucch_formats, l), ls }
+        })
+        .collect::<Vec<_>>();
+
+    // h[n_hop][prb][a], LS over the subcarriers of the PRB and the DMRS symbols of the hop
+    let mut cov = Covariance::new(rx.len());
+    let mut h = vec![Vec::new(); 2];
+    for (n_hop, h) in h.iter_mut().enumerate() {
+        let hop_ls = dmrs_ls.iter().filter(|dmrs| dmrs.n_hop == n_hop as u32).collect::<Vec<_>>();
+        if hop_ls.is_empty() {
+            continue;
+        }
+        *h = (0..m_sc / num_sc)
+            .map(|prb| {
+                let ls = (0..rx.len())
+                    .map(|a| hop_ls.iter().flat_map(|dmrs| &dmrs.ls[a][prb * num_sc..(prb + 1) * num_sc]).copied().collect())
+                    .collect::<Vec<Vec<_>>>();
+                ls_channel_est(&ls, &mut cov)
+            })
+            .collect();
+    }
+    let num_hop = h.iter().filter(|h| !h.is_empty()).count();
+    let (sinr_db, measurement) = channel_measurement(h.iter().flatten(), num_sc * dmrs_ls.len() / num_hop, &cov);
+
+    let combiner = cov.combiner(combining);
+    let w = match pucch_resource.format {
+        PucchFormat::Format4 { occ_len, occ_idx, .. } => format4_occ(occ_len, occ_idx),
+        _ => vec![Complex::ONE; m_sc],
+    };
+    let block_len = m_sc / n_sf;
+    let mut d = Vec::new();
+    let mut d_noise_var = Vec::new();
+    for l in (0..num_sym).filter(|l| !dmrs_pos.contains(l)) {
+        let h = &h[pucch_resource.n_hop(pucch_formats, l) as usize];
+        let (eq, gain): (Vec<_>, Vec<_>) = (0..m_sc)
+            .map(|k| combiner.combine(&h[k / num_sc], &rx.iter().map(|rx| rx[l as usize].data[k]).collect::<Vec<_>>()))
+            .unzip();
+        let eq_noise_var = gain.iter().map(|&g| 1f32 / g).sum::<f32>() / m_sc as f32;
+
+        // 38.211, 6.3.2.6.3 and 6.3.2.6.4, the inverse of the transform precoding and the block-wise spreading
+        let scale = 1f32 / (m_sc as f32).sqrt();
+        let y = idft(&eq).iter().map(|&y| y * scale).collect::<Vec<_>>();
+        for i in 0..block_len {
+            d.push((0..n_sf).map(|n| w[n * block_len + i].conj() * y[n * block_len + i]).sum::<Complex>() / n_sf as f32);
+            d_noise_var.push(eq_noise_var / n_sf as f32);
+        }
+    }
+
+    let llr = if pucch_resource.pucch_format_config(pucch_formats).pi2_bpsk {
+        pi2_bpsk_demodulate(&d, &d_noise_var)
+    } else {
+        qpsk_demodulate(&d, &d_noise_var)
+    };
+    let sync = Some(dmrs_sync(&dmrs_ls, &pucch_config.bwp.numerology));
+    Format234Decoded { llr: pucch_descrambling(&llr, params), sinr_db, measurement, sync }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::*;
+    use crate::test_util::*;
+
+    // complex Gaussian noise of variance noise_var
+    fn add_noise(rx: &mut [PucchSymbol], noise_var: f32, seed: u64) {
+        let mut rng = Rng::new(seed);
+        rx.iter_mut().flat_map(|symbol| symbol.data.iter_mut()).for_each(|x| *x += rng.complex_gaussian(noise_var));
+    }
+
+    fn channel(tx: &[PucchSymbol], h: Complex) -> Vec<PucchSymbol> {
+        tx.iter().map(|symbol| PucchSymbol { data: symbol.data.iter().map(|&x| x * h).collect(), ..symbol.clone() }).collect()
+    }
+
+    #[test]
+    fn format0_loopback() {
+        let pucch_config = test_pucch_config(&test_pucch_resources());
+        let resource = PucchResourceId::new(&pucch_config, 10).pucch_resource(&pucch_config);
+        let params = test_params(7);
+        let h = Complex::expj(0.7) * 0.8;
+
+        for (seed, (harq_ack, sr)) in format0_uci_candidates(2, true).into_iter().enumerate() {
+            let mut rx = channel(&pucch_format0_gen(&pucch_config, resource, &params, &harq_ack, sr), h);
+            add_noise(&mut rx, 0.064, seed as u64 + 1);
+            let decoded = pucch_format0_rx(&pucch_config, resource, &params, &[rx], 2, true, 0.4);
+            assert!(!decoded.dtx && decoded.metric > 0.8, "{:?}", decoded);
+            assert_eq!((decoded.harq_ack, decoded.sr), (harq_ack, sr));
+        }
+
+        // positive SR only, and DTX
+        let mut rx = channel(&pucch_format0_gen(&pucch_config, resource, &params, &[], true), h);
+        add_noise(&mut rx, 0.064, 100);
+        let decoded = pucch_format0_rx(&pucch_config, resource, &params, &[rx], 0, true, 0.4);
+        assert!(!decoded.dtx && decoded.sr && decoded.harq_ack.is_empty());
+        for seed in 200..210 {
+            let mut rx = channel(&pucch_format0_gen(&pucch_config, resource, &params, &[1], false), Complex::ZERO);
+            add_noise(&mut rx, 1f32, seed);
+            let decoded = pucch_format0_rx(&pucch_config, resource, &params, &[rx], 1, true, 0.4);
+            assert!(decoded.dtx && !decoded.sr && decoded.harq_ack.is_empty(), "{:?}", decoded);
+        }
+    }
+
+    #[test]
+    fn format1_loopback() {
+        let pucch_config = test_pucch_config(&test_pucch_resources());
+        let resource = PucchResourceId::new(&pucch_config, 11).pucch_resource(&pucch_config);
+        let other = PucchResourceId::new(&pucch_config, 12).pucch_resource(&pucch_config);
+        let params = test_params(5);
+
+        for (seed, bits) in [vec![0], vec![1], vec![0, 1], vec![1, 1], vec![1, 0]].iter().enumerate() {
+            // another UE with the other OCC on the same cyclic shift
+            let tx = channel(&pucch_format1_gen(&pucch_config, resource, &params, bits), Complex::expj(-2.1) * 0.7);
+            let mut rx = channel(&pucch_format1_gen(&pucch_config, other, &params, &[1, 0]), Complex::expj(1.3));
+            rx.iter_mut().zip(&tx).for_each(|(a, b)| a.data.iter_mut().zip(&b.data).for_each(|(x, &y)| *x += y));
+            add_noise(&mut rx, 0.1, seed as u64 + 1);
+
+            let decoded = pucch_format1_rx(&pucch_config, resource, &params, &[rx], bits.len(), Combining::Mrc, 0.2);
+            assert!(!decoded.dtx, "{:?}", decoded);
+            assert_eq!(decoded.llr.iter().map(|&llr| (llr < 0f32) as u8).collect::<Vec<_>>(), *bits);
+            assert!(decoded.llr.iter().all(|llr| llr.abs() > 10f32), "{:?}", decoded);
+        }
+
+        // SR on-off keying
+        let mut rx = channel(&pucch_format1_gen(&pucch_config, resource, &params, &[0]), Complex::expj(0.4));
+        add_noise(&mut rx, 0.5, 10);
+        assert!(pucch_format1_sr_rx(&pucch_config, resource, &params, &[rx], 0.2));
+        for seed in 20..30 {
+            let mut rx = channel(&pucch_format1_gen(&pucch_config, resource, &params, &[0]), Complex::ZERO);
+            add_noise(&mut rx, 1f32, seed);
+            assert!(!pucch_format1_sr_rx(&pucch_config, resource, &params, &[rx], 0.2));
+        }
+    }
+
+    fn hard_decision(llr: &[f32]) -> Vec<u8> {
+        llr.iter().map(|&llr| (llr < 0f32) as u8).collect()
+    }
+
+    #[test]
+    fn format234_loopback() {
+        let pucch_config = test_pucch_config(&test_pucch_resources());
+        let params = test_params(11);
+        let pucch_formats = &pucch_config.pucch_formats;
+        let bits_of = |len: u32, seed: u32| (0..len).map(|i| ((i * 7 + seed) % 5 % 2) as u8).collect::<Vec<_>>();
+
+        // format 2 on 2 PRBs with hopping, format 3 pi/2-BPSK on 10 PRBs with hopping
+        let resource = PucchResourceId::new(&pucch_config, 2).pucch_resource(&pucch_config);
+        let bits = bits_of(resource.e_tot(pucch_formats), 1);
+        let mut rx = channel(&pucch_format2_gen(&pucch_config, resource, &params, &bits), Complex::expj(2.5) * 1.2);
+        add_noise(&mut rx, 0.05, 1);
+        let llr = pucch_format2_rx(&pucch_config, resource, &params, &[rx], Combining::Mrc).llr;
+        assert_eq!(hard_decision(&llr), bits);
+
+        let resource = PucchResourceId::new(&pucch_config, 3).pucch_resource(&pucch_config);
+        let bits = bits_of(resource.e_tot(pucch_formats), 2);
+        let mut rx = channel(&pucch_format34_gen(&pucch_config, resource, &params, &bits), Complex::expj(-0.3) * 0.9);
+        add_noise(&mut rx, 0.05, 2);
+        let llr = pucch_format34_rx(&pucch_config, resource, &params, &[rx], Combining::Mrc).llr;
+        assert_eq!(llr.len(), 480);
+        assert_eq!(hard_decision(&llr), bits);
+
+        // two UEs of format 4 QPSK with OCC index 0 and 1 on the same PRB
+        let resource = PucchResourceId::new(&pucch_config, 13).pucch_resource(&pucch_config);
+        let other = PucchResourceId::new(&pucch_config, 14).pucch_resource(&pucch_config);
+        let bits = bits_of(resource.e_tot(pucch_formats), 3);
+        let other_bits = bits_of(other.e_tot(pucch_formats), 4);
+        let tx = channel(&pucch_format34_gen(&pucch_config, resource, &params, &bits), Complex::expj(1.0));
+        let mut rx = channel(&pucch_format34_gen(&pucch_config, other, &params, &other_bits), Complex::expj(-2.0) * 0.8);
+        rx.iter_mut().zip(&tx).for_each(|(a, b)| a.data.iter_mut().zip(&b.data).for_each(|(x, &y)| *x += y));
+        add_noise(&mut rx, 0.02, 3);
+        let rx = [rx];
+        let llr = pucch_format34_rx(&pucch_config, resource, &params, &rx, Combining::Mrc).llr;
+        assert_eq!(llr.len() as u32, resource.e_tot(pucch_formats));
+        assert_eq!(hard_decision(&llr), bits);
+        assert_eq!(hard_decision(&pucch_format34_rx(&pucch_config, other, &params, &rx, Combining::Irc).llr), other_bits);
+    }
+
+    #[test]
+    fn format2_dmrs_from_crb0() {
+        // the BWP starts at CRB 24, the DMRS index counts from CRB 0
+        let mut pucch_config_rrc = test_pucch_config_rrc(&[]);
+        pucch_config_rrc.bwp.get_or_insert_with(BwpR::default).start_crb = 24;
+        let pucch_config: PucchConfig = pucch_config_rrc.into();
+        let num_sym_per_slot = pucch_config.bwp.numerology.num_sym_per_slot();
+        let params = test_params(3);
+        let resource = PucchResourceId::new(&pucch_config, 2).pucch_resource(&pucch_config);
+        let bits = (0..resource.e_tot(&pucch_config.pucch_formats)).map(|i| (i % 3 % 2) as u8).collect::<Vec<_>>();
+        let tx = pucch_format2_gen(&pucch_config, resource, &params, &bits);
+        for symbol in &tx {
+            let dmrs = symbol.data.iter().enumerate().filter(|&(k, _)| is_format2_dmrs_sc(k)).map(|(_, &x)| x);
+            let m_start = 4 * (24 + symbol.start_prb) as usize;
+            let dmrs_ref = format2_dmrs(&params, num_sym_per_slot, symbol.sym, m_start, dmrs.clone().count());
+            assert!(dmrs.zip(dmrs_ref).all(|(x, r)| (x - r).abs() < 1e-6));
+        }
+
+        let mut rx = channel(&tx, Complex::expj(0.7));
+        add_noise(&mut rx, 0.05, 5);
+        let llr = pucch_format2_rx(&pucch_config, resource, &params, &[rx], Combining::Mrc).llr;
+        assert_eq!(hard_decision(&llr), bits);
+    }
+
+    #[test]
+    fn format2_irc() {
+        let pucch_config = test_pucch_config(&test_pucch_resources());
+        let resource = PucchResourceId::new(&pucch_config, 2).pucch_resource(&pucch_config);
+        let params = test_params(3);
+        let interferer_params = PucchTxParams { n_rnti: 0x1234, dmrs_scrambling_id: Some(500), ..params };
+        let e_tot = resource.e_tot(&pucch_config.pucch_formats);
+        let bits = (0..e_tot).map(|i| (i * 5 % 7 % 2) as u8).collect::<Vec<_>>();
+        let tx = pucch_format2_gen(&pucch_config, resource, &params, &bits);
+        let interferer = pucch_format2_gen(&pucch_config, resource, &interferer_params, &vec![0; e_tot as usize]);
+
+        // 2 antennas, the interferer is 10 dB stronger than the UE
+        let rx = [(Complex::ONE, Complex::expj(2f32) * 3.2), (Complex::expj(1.5), Complex::expj(-0.3) * 3.2)]
+            .iter()
+            .enumerate()
+            .map(|(a, &(h, v))| {
+                let mut rx = channel(&tx, h);
+                let i = channel(&interferer, v);
+                rx.iter_mut().zip(&i).for_each(|(a, b)| a.data.iter_mut().zip(&b.data).for_each(|(x, &y)| *x += y));
+                add_noise(&mut rx, 0.01, a as u64 + 1);
+                rx
+            })
+            .collect::<Vec<_>>();
+
+        let irc = pucch_format2_rx(&pucch_config, resource, &params, &rx, Combining::Irc);
+        assert_eq!(hard_decision(&irc.llr), bits);
+        assert_eq!(irc.sinr_db.len(), 2);
+        let mrc = pucch_format2_rx(&pucch_config, resource, &params, &rx, Combining::Mrc);
+        assert_ne!(hard_decision(&mrc.llr), bits);
+    }
+
+    #[test]
+    fn dmrs_sync_estimation() {
+        let pucch_config = test_pucch_config(&test_pucch_resources());
+        let numerology = pucch_config.bwp.numerology;
+        let params = test_params(9);
+        let channel_config =
+            ChannelConfig { num_rx: 2, snr_db: 15f32, timing_offset_ns: 800f32, cfo_hz: 400f32, ..Default::default() };
+
+        // format 1 with hopping, 4 DMRS symbols per hop
+        let resource = PucchResourceId::new(&pucch_config, 11).pucch_resource(&pucch_config);
+        let tx = pucch_format1_gen(&pucch_config, resource, &params, &[1, 0]);
+        let rx = Channel::new(&channel_config, numerology, 1).apply(&tx, 0);
+        let decoded = pucch_format1_rx(&pucch_config, resource, &params, &rx, 2, Combining::Mrc, 0.2);
+        assert_eq!(hard_decision(&decoded.llr), [1, 0]);
+        let sync = decoded.sync.unwrap();
+        assert!((sync.timing_offset_s - 800e-9).abs() < 100e-9, "{:?}", sync);
+        assert!((sync.cfo_hz.unwrap() - 400f32).abs() < 40f32, "{:?}", sync);
+
+        // format 3 without hopping on DMRS symbols 3 and 10, and with hopping of one DMRS symbol per hop
+        let resource = PucchResourceId::new(&pucch_config, 15).pucch_resource(&pucch_config);
+        let bits = (0..resource.e_tot(&pucch_config.pucch_formats)).map(|i| (i % 3 % 2) as u8).collect::<Vec<_>>();
+        let tx = pucch_format34_gen(&pucch_config, resource, &params, &bits);
+        let rx = Channel::new(&channel_config, numerology, 2).apply(&tx, 0);
+        let sync = pucch_format34_rx(&pucch_config, resource, &params, &rx, Combining::Mrc).sync.unwrap();
+        assert!((sync.timing_offset_s - 800e-9).abs() < 100e-9, "{:?}", sync);
+        assert!((sync.cfo_hz.unwrap() - 400f32).abs() < 40f32, "{:?}", sync);
+
+        let resource = PucchResourceId::new(&pucch_config, 3).pucch_resource(&pucch_config);
+        let bits = (0..resource.e_tot(&pucch_config.pucch_formats)).map(|i| (i % 3 % 2) as u8).collect::<Vec<_>>();
+        let rx = Channel::new(&channel_config, numerology, 3)
+            .apply(&pucch_format34_gen(&pucch_config, resource, &params, &bits), 0);
+        let sync = pucch_format34_rx(&pucch_config, resource, &params, &rx, Combining::Mrc).sync.unwrap();
+        assert!((sync.timing_offset_s - 800e-9).abs() < 100e-9 && sync.cfo_hz.is_none(), "{:?}", sync);
+    }
+
+    #[test]
+    fn measurement() {
+        let pucch_config = test_pucch_config(&test_pucch_resources());
+        let numerology = pucch_config.bwp.numerology;
+        let pucch_formats = &pucch_config.pucch_formats;
+        // the DMRS scrambled with the configured ID instead of the cell ID
+        let params = PucchTxParams { dmrs_scrambling_id: Some(17), ..test_params(4) };
+        let channel_config = ChannelConfig { num_rx: 2, snr_db: 10f32, ..Default::default() };
+        let f0 = PucchResourceId::new(&pucch_config, 10).pucch_resource(&pucch_config);
+        let f1 = PucchResourceId::new(&pucch_config, 11).pucch_resource(&pucch_config);
+        let f2 = PucchResourceId::new(&pucch_config, 2).pucch_resource(&pucch_config);
+        let f3 = PucchResourceId::new(&pucch_config, 15).pucch_resource(&pucch_config);
+        let f2_bits = vec![1; f2.e_tot(pucch_formats) as usize];
+        let f3_bits = vec![0; f3.e_tot(pucch_formats) as usize];
+
+        for (seed, dtx) in [(1, false), (2, true)] {
+            let gain = if dtx { 0f32 } else { 1f32 };
+            let mut tx = [
+                pucch_format0_gen(&pucch_config, f0, &params, &[1], false),
+                pucch_format1_gen(&pucch_config, f1, &params, &[1]),
+                pucch_format2_gen(&pucch_config, f2, &params, &f2_bits),
+                pucch_format34_gen(&pucch_config, f3, &params, &f3_bits),
+            ];
+            tx.iter_mut().flatten().flat_map(|symbol| symbol.data.iter_mut()).for_each(|x| *x *= gain);
+            let mut channel = Channel::new(&channel_config, numerology, seed);
+            let rx = tx.iter().map(|tx| channel.apply(tx, 0)).collect::<Vec<_>>();
+            let measurements = [
+                pucch_format0_rx(&pucch_config, f0, &params, &rx[0], 1, false, 0.4).measurement,
+                pucch_format1_rx(&pucch_config, f1, &params, &rx[1], 1, Combining::Mrc, 0.2).measurement,
+                pucch_format2_rx(&pucch_config, f2, &params, &rx[2], Combining::Mrc).measurement,
+                pucch_format34_rx(&pucch_config, f3, &params, &rx[3], Combining::Mrc).measurement,
+            ];
+            for m in measurements {
+                assert!((m.noise_var / 0.1 - 1f32).abs() < 0.3, "{:?}", m);
+                if dtx {
+                    assert!(m.dtx_confidence > 0.7 && m.sinr_db < 0f32, "{:?}", m);
+                } else {
+                    assert!(m.rsrp_db.abs() < 1f32 && (m.sinr_db - 10f32).abs() < 1.5 && m.dtx_confidence < 0.15, "{:?}", m);
+                }
+            }
+        }
+    }
+}
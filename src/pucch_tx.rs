@@ -0,0 +1,373 @@
+use crate::complex::*;
+use crate::constants::*;
+use crate::dft::*;
+use crate::gold::*;
+use crate::modulation::*;
+use crate::pucch::*;
+use crate::rrc_pucch::*;
+use crate::sequence::*;
+use std::f32::consts::PI;
+
+// slot level parameters of a PUCCH transmission
+#[derive(Debug, Clone, Copy)]
+pub struct PucchTxParams {
+    pub slot: u32,                       // n_s^mu_f, slot number in the frame
+    pub cell_id: u32,                    // N_ID^cell
+    pub n_rnti: u32,                     // C-RNTI
+    pub data_scrambling_id: Option<u32>, // dataScramblingIdentityPUSCH
+    pub dmrs_scrambling_id: Option<u32>, // scramblingID0 of DMRS-UplinkConfig
+}
+
+impl PucchTxParams {
+    // 38.211, 6.3.2.5.1 and 6.3.2.6.1, n_ID of the scrambling
+    pub fn n_id_scrambling(&self) -> u32 {
+        self.data_scrambling_id.unwrap_or(self.cell_id)
+    }
+
+    // 38.211, 6.4.1.3.2.1, N_ID^0 of the format 2 DMRS
+    pub fn n_id_0(&self) -> u32 {
+        self.dmrs_scrambling_id.unwrap_or(self.cell_id)
+    }
+}
+
+// frequency domain symbols of one OFDM symbol, the subcarriers start from start_prb
+#[derive(Debug, Clone, PartialEq)]
+pub struct PucchSymbol {
+    pub sym: u32, // symbol index in the slot
+    pub start_prb: u32,
+    pub data: Vec<Complex>,
+}
+
+// 38.211, 6.3.2.2, low-PAPR sequence r_u,v^(alpha, delta)(n) of length len on symbol l counted from the first PUCCH symbol
+pub fn pucch_base_seq(
+    pucch_config: &PucchConfig,
+    pucch_resource: &PucchResource,
+    params: &PucchTxParams,
+    l: u32,
+    m_cs: u32,
+    len: usize,
+) -> Vec<Complex> {
+    let hopping = &pucch_config.pucch_group_seq_hopping;
+    let n_id = pucch_hopping_id(hopping, params.cell_id);
+    let num_sym_per_slot = pucch_config.bwp.numerology.num_sym_per_slot();
+    let n_hop = pucch_resource.n_hop(&pucch_config.pucch_formats, l);
+    let (u, v) = pucch_group_seq(hopping, params.cell_id, params.slot, n_hop);
+    let alpha = pucch_alpha(&pucch_resource.format, n_id, num_sym_per_slot, params.slot, l, m_cs);
+    low_papr_seq(u, v, alpha, len)
+}
+
+// 38.211, 6.3.2.3, PUCCH format 0, harq_ack is empty for positive SR only
+pub fn pucch_format0_gen(
+    pucch_config: &PucchConfig,
+    pucch_resource: &PucchResource,
+    params: &PucchTxParams,
+    harq_ack: &[u8],
+    sr: bool,
+) -> Vec<PucchSymbol> {
+    assert!(matches!(pucch_resource.format, PucchFormat::Format0 { .. }), "not PUCCH format 0!");
+
+    let m_cs = format0_m_cs(harq_ack, sr);
+    let (start_sym, num_sym) = pucch_resource.occupied_sym();
+    (0..num_sym)
+        .map(|l| PucchSymbol {
+            sym: start_sym + l,
+            start_prb: pucch_resource.sym_start_prb(&pucch_config.pucch_formats, l, 0),
+            data: pucch_base_seq(pucch_config, pucch_resource, params, l, m_cs, NUM_SC_PER_RB as usize),
+        })
+        .collect()
+}
+
+// 38.211, Table 6.3.2.4.1-2, phi(m) of w_i(m) = exp(j * 2 * pi * phi(m) / N_SF), N_SF = 4
+const FORMAT1_OCC_PHI_SF_4: [[u32; 4]; 4] = [[0, 0, 0, 0], [0, 2, 0, 2], [0, 0, 2, 2], [0, 2, 2, 0]];
+
+// 38.211, 6.3.2.4.1 and 6.4.1.3.1, w_i(m) on symbol l counted from the first PUCCH symbol
+// DMRS is on the even symbols and UCI on the odd symbols, the OCC spans the symbols of the same kind in the hop
+pub fn format1_time_occ(pucch_formats: &PucchFormatsConfig, pucch_resource: &PucchResource, l: u32) -> Complex {
+    let (time_occ, num_sym) = match pucch_resource.format {
+        PucchFormat::Format1 { time_occ, num_sym, .. } => (time_occ, num_sym),
+        _ => panic!("not PUCCH format 1!"),
+    };
+    let n_hop = pucch_resource.n_hop(pucch_formats, l);
+    let same_kind =
+        (0..num_sym).filter(|&sym| sym % 2 == l % 2 && pucch_resource.n_hop(pucch_formats, sym) == n_hop).collect::<Vec<_>>();
+    let n_sf = same_kind.len() as u32;
+    let m = same_kind.iter().position(|&sym| sym == l).unwrap() as u32;
+    assert!(time_occ < n_sf, "time domain OCC {} exceeds spreading factor {}!", time_occ, n_sf);
+
+    let phi = if n_sf == 4 { FORMAT1_OCC_PHI_SF_4[time_occ as usize][m as usize] } else { (time_occ * m) % n_sf };
+    Complex::expj(2f32 * PI * phi as f32 / n_sf as f32)
+}
+
+// 38.211, 6.3.2.4 and 6.4.1.3.1, PUCCH format 1 with its DMRS, bits are the 1 or 2 HARQ-ACK bits, [0] for positive SR only
+pub fn pucch_format1_gen(
+    pucch_config: &PucchConfig,
+    pucch_resource: &PucchResource,
+    params: &PucchTxParams,
+    bits: &[u8],
+) -> Vec<PucchSymbol> {
+    assert!(matches!(pucch_resource.format, PucchFormat::Format1 { .. }), "not PUCCH format 1!");
+    let d = match bits {
+        [bit] => bpsk(*bit),
+        [bit0, bit1] => qpsk(*bit0, *bit1),
+        _ => panic!("invalid PUCCH format 1 payload {:?}!", bits),
+    };
+
+    let (start_sym, num_sym) = pucch_resource.occupied_sym();
+    (0..num_sym)
+        .map(|l| {
+            let w = format1_time_occ(&pucch_config.pucch_formats, pucch_resource, l);
+            let scale = if l % 2 == 0 { w } else { w * d };
+            let base_seq = pucch_base_seq(pucch_config, pucch_resource, params, l, 0, NUM_SC_PER_RB as usize);
+            PucchSymbol {
+                sym: start_sym + l,
+                start_prb: pucch_resource.sym_start_prb(&pucch_config.pucch_formats, l, 0),
+                data: base_seq.iter().map(|&r| scale * r).collect(),
+            }
+        })
+        .collect()
+}
+
+// 38.211, 6.3.2.5.1 and 6.3.2.6.1, scrambling of the coded bits
+pub fn pucch_scrambling(bits: &[u8], params: &PucchTxParams) -> Vec<u8> {
+    let c = gold_seq(c_init_pucch_scrambling(params.n_rnti, params.n_id_scrambling()), 0, bits.len());
+    bits.iter().zip(&c).map(|(&bit, &c)| bit ^ c).collect()
+}
+
+// 38.211, 6.4.1.3.2.2, DMRS on subcarrier 3m + 1 and UCI on the others
+pub fn is_format2_dmrs_sc(k: usize) -> bool {
+    k % 3 == 1
+}
+
+// 38.211, 6.4.1.3.2.1, DMRS sequence r_l(m) of format 2 on symbol sym of the slot, from DMRS index m_start
+// m counts from subcarrier 0 of the CRB 0, so m_start is 4 * n_CRB of the first PRB, the number of DMRS is 4 per PRB
+pub fn format2_dmrs(params: &PucchTxParams, num_sym_per_slot: u32, sym: u32, m_start: usize, len: usize) -> Vec<Complex> {
+    let c = gold_seq(c_init_pucch_dmrs(params.n_id_0(), num_sym_per_slot, params.slot, sym), 2 * m_start, 2 * len);
+    qpsk_modulate(&c)
+}
+
+// 38.211, 6.3.2.5 and 6.4.1.3.2, PUCCH format 2 with its DMRS, bits are the E_tot coded bits
+pub fn pucch_format2_gen(
+    pucch_config: &PucchConfig,
+    pucch_resource: &PucchResource,
+    params: &PucchTxParams,
+    bits: &[u8],
+) -> Vec<PucchSymbol> {
+    let num_rb = match pucch_resource.format {
+        PucchFormat::Format2 { num_rb, .. } => num_rb as usize,
+        _ => panic!("not PUCCH format 2!"),
+    };
+    let (start_sym, num_sym) = pucch_resource.occupied_sym();
+    let num_sc = num_rb * NUM_SC_PER_RB as usize;
+    let num_data_sc = num_rb * (NUM_SC_PER_RB - 4) as usize;
+    assert_eq!(bits.len(), 2 * num_data_sc * num_sym as usize, "invalid PUCCH format 2 payload size!");
+
+    // 38.211, 6.3.2.5.2 and 6.3.2.5.3, QPSK mapped in increasing order of k then l
+    let d = qpsk_modulate(&pucch_scrambling(bits, params));
+    d.chunks(num_data_sc)
+        .enumerate()
+        .map(|(l, d)| {
+            let sym = start_sym + l as u32;
+            let start_prb = pucch_resource.sym_start_prb(&pucch_config.pucch_formats, l as u32, 0);
+            let m_start = 4 * pucch_config.bwp.prb_to_crb(start_prb) as usize;
+            let mut dmrs =
+                format2_dmrs(params, pucch_config.bwp.numerology.num_sym_per_slot(), sym, m_start, 4 * num_rb).into_iter();
+            let mut d = d.iter();
+            let data =
+                (0..num_sc).map(|k| if is_format2_dmrs_sc(k) { dmrs.next().unwrap() } else { *d.next().unwrap() }).collect();
+            PucchSymbol { sym, start_prb, data }
+        })
+        .collect()
+}
+
+// 38.211, Table 6.3.2.6.3-1 and Table 6.3.2.6.3-2, w_n(k) of PUCCH format 4, constant over each M_sc / N_SF subcarriers
+pub fn format4_occ(occ_len: u32, occ_idx: u32) -> Vec<Complex> {
+    const OCC_LEN_2: [[Complex; 2]; 2] = [[Complex::ONE, Complex::ONE], [Complex::ONE, Complex::new(-1f32, 0f32)]];
+    const OCC_LEN_4: [[Complex; 4]; 4] = [
+        [Complex::ONE, Complex::ONE, Complex::ONE, Complex::ONE],
+        [Complex::ONE, Complex::new(0f32, -1f32), Complex::new(-1f32, 0f32), Complex::I],
+        [Complex::ONE, Complex::new(-1f32, 0f32), Complex::ONE, Complex::new(-1f32, 0f32)],
+        [Complex::ONE, Complex::I, Complex::new(-1f32, 0f32), Complex::new(0f32, -1f32)],
+    ];
+    let w: &[Complex] = match occ_len {
+        2 => &OCC_LEN_2[occ_idx as usize],
+        4 => &OCC_LEN_4[occ_idx as usize],
+        _ => panic!("invalid occ length {}!", occ_len),
+    };
+    let block_len = NUM_SC_PER_RB / occ_len;
+    w.iter().flat_map(|&w| (0..block_len).map(move |_| w)).collect()
+}
+
+// (M_sc, N_SF) of PUCCH format 3/4
+pub fn format34_sc_sf(pucch_resource: &PucchResource) -> (usize, usize) {
+    match pucch_resource.format {
+        PucchFormat::Format3 { num_rb, .. } => ((num_rb * NUM_SC_PER_RB) as usize, 1),
+        PucchFormat::Format4 { occ_len, .. } => (NUM_SC_PER_RB as usize, occ_len as usize),
+        _ => panic!("not PUCCH format 3/4!"),
+    }
+}
+
+// 38.211, 6.3.2.6 and 6.4.1.3.3, PUCCH format 3/4 with its DMRS, bits are the E_tot coded bits
+pub fn pucch_format34_gen(
+    pucch_config: &PucchConfig,
+    pucch_resource: &PucchResource,
+    params: &PucchTxParams,
+    bits: &[u8],
+) -> Vec<PucchSymbol> {
+    let (m_sc, n_sf) = format34_sc_sf(pucch_resource);
+    assert!(is_dft_size(m_sc), "invalid PUCCH format 3 size {}!", m_sc);
+    let pucch_formats = &pucch_config.pucch_formats;
+    assert_eq!(bits.len() as u32, pucch_resource.e_tot(pucch_formats), "invalid PUCCH format 3/4 payload size!");
+
+    // 38.211, 6.3.2.6.1 and 6.3.2.6.2
+    let scrambled = pucch_scrambling(bits, params);
+    let d = if pucch_resource.pucch_format_config(pucch_formats).pi2_bpsk {
+        pi2_bpsk_modulate(&scrambled)
+    } else {
+        qpsk_modulate(&scrambled)
+    };
+    let w = match pucch_resource.format {
+        PucchFormat::Format4 { occ_len, occ_idx, .. } => format4_occ(occ_len, occ_idx),
+        _ => vec![Complex::ONE; m_sc],
+    };
+
+    let dmrs_pos = pucch_resource.pucch_dmrs_pos(pucch_formats);
+    let (start_sym, num_sym) = pucch_resource.occupied_sym();
+    let mut d_blocks = d.chunks(m_sc / n_sf);
+    (0..num_sym)
+        .map(|l| {
+            let data = if dmrs_pos.contains(&l) {
+                // 38.211, 6.4.1.3.3.1
+                pucch_base_seq(pucch_config, pucch_resource, params, l, 0, m_sc)
+            } else {
+                // 38.211, 6.3.2.6.3 and 6.3.2.6.4, block-wise spreading then transform precoding
+                let d_block = d_blocks.next().unwrap();
+                let y = w.iter().enumerate().map(|(k, &w)| w * d_block[k % d_block.len()]).collect::<Vec<_>>();
+                let scale = 1f32 / (m_sc as f32).sqrt();
+                dft(&y).iter().map(|&z| z * scale).collect()
+            };
+            PucchSymbol { sym: start_sym + l, start_prb: pucch_resource.sym_start_prb(pucch_formats, l, 0), data }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+
+    fn corr(a: &[Complex], b: &[Complex]) -> f32 {
+        a.iter().zip(b).map(|(&x, &y)| x * y.conj()).sum::<Complex>().abs()
+    }
+
+    #[test]
+    fn format0_gen() {
+        let pucch_config = test_pucch_config(&test_pucch_resources());
+        let resource = PucchResourceId::new(&pucch_config, 10).pucch_resource(&pucch_config);
+        let params = test_params(7);
+
+        let ack = pucch_format0_gen(&pucch_config, resource, &params, &[1], false);
+        let nack = pucch_format0_gen(&pucch_config, resource, &params, &[0], false);
+        assert_eq!(ack.iter().map(|symbol| (symbol.sym, symbol.start_prb)).collect::<Vec<_>>(), [(12, 5), (13, 30)]);
+        for (ack, nack) in ack.iter().zip(&nack) {
+            assert_eq!(ack.data.len(), NUM_SC_PER_RB as usize);
+            assert!(corr(&ack.data, &nack.data) < 1e-4);
+            assert!((corr(&ack.data, &ack.data) - NUM_SC_PER_RB as f32).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn format1_gen() {
+        let pucch_config = test_pucch_config(&test_pucch_resources());
+        let params = test_params(3);
+        let gen = |id, bits: &[u8]| {
+            let resource = PucchResourceId::new(&pucch_config, id).pucch_resource(&pucch_config);
+            pucch_format1_gen(&pucch_config, resource, &params, bits)
+        };
+
+        let (ue0, ue1) = (gen(11, &[1, 0]), gen(12, &[0, 0]));
+        assert_eq!(ue0.len(), 14);
+        // different time domain OCC are orthogonal on both UCI and DMRS symbols
+        for first_sym in [0, 1] {
+            let cross = (first_sym..14)
+                .step_by(2)
+                .map(|l| ue0[l].data.iter().zip(&ue1[l].data).map(|(&x, &y)| x * y.conj()).sum::<Complex>())
+                .sum::<Complex>();
+            assert!(cross.abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn format2_gen() {
+        let pucch_config = test_pucch_config(&test_pucch_resources());
+        // 2 PRB, 2 symbols with intra slot hopping
+        let resource = PucchResourceId::new(&pucch_config, 2).pucch_resource(&pucch_config);
+        let params = test_params(9);
+        let bits = (0..64).map(|i| (i % 3 % 2) as u8).collect::<Vec<_>>();
+        let symbols = pucch_format2_gen(&pucch_config, resource, &params, &bits);
+        assert_eq!(symbols.iter().map(|symbol| (symbol.sym, symbol.start_prb)).collect::<Vec<_>>(), [(4, 0), (5, 20)]);
+
+        let mut rx_bits = vec![];
+        for symbol in &symbols {
+            assert_eq!(symbol.data.len(), 24);
+            for (_, x) in symbol.data.iter().enumerate().filter(|&(k, _)| !is_format2_dmrs_sc(k)) {
+                rx_bits.extend([(x.re < 0f32) as u8, (x.im < 0f32) as u8]);
+            }
+            let dmrs = symbol.data.iter().enumerate().filter(|&(k, _)| is_format2_dmrs_sc(k)).map(|(_, &x)| x);
+            let dmrs_ref = format2_dmrs(
+                &params,
+                pucch_config.bwp.numerology.num_sym_per_slot(),
+                symbol.sym,
+                4 * pucch_config.bwp.prb_to_crb(symbol.start_prb) as usize,
+                8,
+            );
+            assert!(dmrs.zip(dmrs_ref).all(|(x, r)| (x - r).abs() < 1e-6));
+        }
+        assert_eq!(pucch_scrambling(&rx_bits, &params), bits);
+    }
+
+    #[test]
+    fn format3_gen() {
+        let pucch_config = test_pucch_config(&test_pucch_resources());
+        // 10 PRB, 6 symbols from symbol 6 with intra slot hopping, DMRS on symbol 1 and 4, pi/2-BPSK
+        let resource = PucchResourceId::new(&pucch_config, 3).pucch_resource(&pucch_config);
+        let params = test_params(1);
+        let bits = (0..480).map(|i| (i % 7 % 2) as u8).collect::<Vec<_>>();
+        let symbols = pucch_format34_gen(&pucch_config, resource, &params, &bits);
+        assert_eq!(symbols.iter().map(|symbol| symbol.start_prb).collect::<Vec<_>>(), [10, 10, 10, 40, 40, 40]);
+
+        let mut rx_bits = vec![];
+        for (l, symbol) in symbols.iter().enumerate() {
+            assert_eq!(symbol.data.len(), 120);
+            if l == 1 || l == 4 {
+                assert!(symbol.data.iter().all(|x| (x.abs() - 1f32).abs() < 1e-5));
+                continue;
+            }
+            let d = idft(&symbol.data);
+            // pi/2-BPSK, the odd symbols are rotated by pi/2
+            let offset = rx_bits.len();
+            rx_bits.extend(d.iter().enumerate().map(|(i, &d)| {
+                let d = if (offset + i) % 2 == 0 { d } else { d * Complex::new(0f32, -1f32) };
+                (d.re < 0f32) as u8
+            }));
+        }
+        assert_eq!(pucch_scrambling(&rx_bits, &params), bits);
+    }
+
+    #[test]
+    fn format4_gen() {
+        let pucch_config = test_pucch_config(&test_pucch_resources());
+        let params = test_params(2);
+        let gen = |id| {
+            let resource = PucchResourceId::new(&pucch_config, id).pucch_resource(&pucch_config);
+            let bits = (0..resource.e_tot(&pucch_config.pucch_formats)).map(|i| (i % 5 % 2) as u8).collect::<Vec<_>>();
+            pucch_format34_gen(&pucch_config, resource, &params, &bits)
+        };
+
+        // different OCC index are on different subcarrier combs, orthogonal on both UCI and DMRS
+        let (ue0, ue1) = (gen(13), gen(14));
+        for (x0, x1) in ue0.iter().zip(&ue1) {
+            assert_eq!(x0.data.len(), 12);
+            assert!(x0.data.iter().zip(&x1.data).map(|(&a, &b)| a * b.conj()).sum::<Complex>().abs() < 1e-4);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+// 38.212, Table 5.3.3.3-1, basis sequences for (32, K) code
+#[rustfmt::skip]
+const RM_BASIS: [[u8; 11]; 32] = [
+    [1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+    [1, 1, 1, 0, 0, 0, 0, 0, 0, 1, 1],
+    [1, 0, 0, 1, 0, 0, 1, 0, 1, 1, 1],
+    [1, 0, 1, 1, 0, 0, 0, 0, 1, 0, 1],
+    [1, 1, 1, 1, 0, 0, 0, 1, 0, 0, 1],
+    [1, 1, 0, 0, 1, 0, 1, 1, 1, 0, 1],
+    [1, 0, 1, 0, 1, 0, 1, 0, 1, 1, 1],
+    [1, 0, 0, 1, 1, 0, 0, 1, 1, 0, 1],
+    [1, 1, 0, 1, 1, 0, 0, 1, 0, 1, 1],
+    [1, 0, 1, 1, 1, 0, 1, 0, 0, 1, 1],
+    [1, 0, 1, 0, 0, 1, 1, 1, 0, 1, 1],
+    [1, 1, 1, 0, 0, 1, 1, 0, 1, 0, 1],
+    [1, 0, 0, 1, 0, 1, 0, 1, 1, 1, 1],
+    [1, 1, 0, 1, 0, 1, 0, 1, 0, 1, 1],
+    [1, 0, 0, 0, 1, 1, 0, 1, 0, 0, 1],
+    [1, 1, 0, 0, 1, 1, 1, 1, 0, 1, 1],
+    [1, 1, 1, 0, 1, 1, 1, 0, 0, 1, 0],
+    [1, 0, 0, 1, 1, 1, 0, 0, 1, 0, 0],
+    [1, 1, 0, 1, 1, 1, 1, 1, 0, 0, 0],
+    [1, 0, 0, 0, 0, 1, 1, 0, 0, 0, 0],
+    [1, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1],
+    [1, 1, 0, 1, 0, 0, 0, 0, 0, 1, 1],
+    [1, 0, 0, 0, 1, 0, 0, 1, 1, 0, 1],
+    [1, 1, 1, 0, 1, 0, 0, 0, 1, 1, 1],
+    [1, 1, 1, 1, 1, 0, 1, 1, 1, 1, 0],
+    [1, 1, 0, 0, 0, 1, 1, 1, 0, 0, 1],
+    [1, 0, 1, 1, 0, 1, 0, 0, 1, 1, 0],
+    [1, 1, 1, 1, 0, 1, 0, 1, 1, 1, 0],
+    [1, 0, 1, 0, 1, 1, 1, 0, 1, 0, 0],
+    [1, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0],
+    [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+    [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+];
+
+const RM_N: usize = 32;
+// M_0 is the all-one sequence and M_1..M_5 enumerate all 5-bit patterns, the first order part is decoded by FHT
+const RM_NUM_LINEAR: usize = 5;
+const RM_MIN_K: u32 = 3;
+const RM_MAX_K: u32 = 11;
+
+// UCI of 3 <= A <= 11 bits, 38.212 5.3.3.3 and 5.4.3
+#[derive(Debug)]
+pub struct ReedMuller {
+    k: usize,
+    e: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RmDecoded {
+    pub bits: Vec<u8>,
+    pub metric: f32, // normalized correlation of the best codeword, in [0, 1]
+    pub dtx: bool,
+}
+
+impl ReedMuller {
+    // e: E_tot of the PUCCH resource, see PucchResource::e_tot
+    pub fn new(k: u32, e: u32) -> Self {
+        assert!((RM_MIN_K..=RM_MAX_K).contains(&k), "invalid Reed-Muller K {}!", k);
+        ReedMuller { k: k as usize, e: e as usize }
+    }
+
+    // 5-bit pattern of M_1..M_5 on each row
+    fn linear_idx(i: usize) -> usize {
+        (0..RM_NUM_LINEAR).map(|n| (RM_BASIS[i][n + 1] as usize) << n).sum()
+    }
+
+    pub fn encode(&self, a: &[u8]) -> Vec<u8> {
+        assert_eq!(a.len(), self.k, "Reed-Muller expects {} bits!", self.k);
+        let d =
+            RM_BASIS.iter().map(|basis| a.iter().zip(basis).fold(0u8, |sum, (&bit, &m)| sum ^ (bit & m))).collect::<Vec<_>>();
+        // 38.212, 5.4.3, rate matching by repetition
+        (0..self.e).map(|k| d[k % RM_N]).collect()
+    }
+
+    // ML decoding: FHT over the first order part for every hypothesis of a_6..a_(K-1)
+    // llr: E soft bits, positive for bit 0, the codeword is declared DTX if the metric is below dtx_threshold
+    pub fn decode(&self, llr: &[f32], dtx_threshold: f32) -> RmDecoded {
+        assert_eq!(llr.len(), self.e, "Reed-Muller expects {} LLRs!", self.e);
+
+        let mut soft = [0f32; RM_N];
+        llr.iter().enumerate().for_each(|(k, &l)| soft[k % RM_N] += l);
+
+        let num_linear = (self.k - 1).min(RM_NUM_LINEAR);
+        let num_high = self.k - 1 - num_linear;
+        // (correlation, a_0, first order bits a_1..a_5, high bits a_6..a_10)
+        let mut best = (f32::MIN, 0u8, 0usize, 0usize);
+        for high in 0..(1usize << num_high) {
+            let mut spectrum = [0f32; RM_N];
+            for (i, &s) in soft.iter().enumerate() {
+                let flip =
+                    (0..num_high).fold(0u8, |sum, n| sum ^ (((high >> n) as u8) & RM_BASIS[i][n + 1 + RM_NUM_LINEAR] & 1));
+                spectrum[ReedMuller::linear_idx(i)] = if flip == 0 { s } else { -s };
+            }
+            fast_hadamard_transform(&mut spectrum);
+            // K < 6 only uses the first K - 1 linear basis sequences
+            for (linear, &corr) in spectrum.iter().enumerate().filter(|&(linear, _)| linear >> num_linear == 0) {
+                let (corr, a0) = if corr >= 0f32 { (corr, 0u8) } else { (-corr, 1u8) };
+                if corr > best.0 {
+                    best = (corr, a0, linear, high);
+                }
+            }
+        }
+
+        let (corr, a0, linear, high) = best;
+        let mut bits = vec![a0];
+        bits.extend((0..num_linear).map(|n| ((linear >> n) & 1) as u8));
+        bits.extend((0..num_high).map(|n| ((high >> n) & 1) as u8));
+
+        let energy = soft.iter().map(|s| s * s).sum::<f32>();
+        let metric = if energy > 0f32 { corr / (RM_N as f32 * energy).sqrt() } else { 0f32 };
+        RmDecoded { bits, metric, dtx: metric < dtx_threshold }
+    }
+}
+
+// in place, unnormalized
+fn fast_hadamard_transform(data: &mut [f32]) {
+    let mut step = 1;
+    while step < data.len() {
+        for i in (0..data.len()).step_by(2 * step) {
+            for j in i..i + step {
+                let (a, b) = (data[j], data[j + step]);
+                data[j] = a + b;
+                data[j + step] = a - b;
+            }
+        }
+        step *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::bits_to_llr;
+
+    #[test]
+    fn reed_muller_loopback() {
+        for k in RM_MIN_K..=RM_MAX_K {
+            let rm = ReedMuller::new(k, 64);
+            for a in (0..1u32 << k).step_by(7) {
+                let bits = (0..k).map(|n| ((a >> n) & 1) as u8).collect::<Vec<_>>();
+                let mut llr = bits_to_llr(&rm.encode(&bits));
+                // 3 bit errors on both repetitions are within the correction capability
+                [0, 9, 17, 32, 41, 49].iter().for_each(|&i| llr[i] = -llr[i]);
+                let decoded = rm.decode(&llr, 0.3);
+                assert_eq!(decoded.bits, bits, "K {}, a {}", k, a);
+                assert!(!decoded.dtx);
+            }
+        }
+    }
+
+    #[test]
+    fn reed_muller_dtx() {
+        let rm = ReedMuller::new(11, 32);
+        assert!(rm.decode(&[0f32; 32], 0.3).dtx);
+        let decoded = rm.decode(&bits_to_llr(&rm.encode(&[1, 0, 1, 1, 0, 0, 0, 1, 1, 0, 1])), 0.3);
+        assert!((decoded.metric - 1f32).abs() < 1e-6);
+    }
+}
@@ -1,23 +1,40 @@
-use crate::err::Error;
+use crate::numerology::CyclicPrefix;
 use core::fmt;
 use serde_derive::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PucchConfigCommonR {
-    index: u32,
-    pucch_group_seq_hopping: PucchGroupSeqHopping,
-    p0_nominal: i32,
+    pub index: u32,
+    pub pucch_group_seq_hopping: PucchGroupSeqHopping,
+    pub p0_nominal: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-enum PucchGroupSeqHopping {
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum PucchGroupSeqHopping {
     Neither,
-    GroupHopping(u32),
-    SeqHopping(u32),
+    GroupHopping(u32), // hoppingId
+    SeqHopping(u32),   // hoppingId
+}
+
+// genericParameters of the uplink BWP
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BwpGenericParametersR {
+    pub subcarrier_spacing: u32, // kHz
+    pub cyclic_prefix: CyclicPrefix,
+}
+
+// uplink BWP, locationAndBandwidth given as start CRB and size
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BwpR {
+    pub start_crb: u32,
+    pub num_rb: u32,
+    pub generic_parameters: BwpGenericParametersR,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PucchConfigR {
+    pub bwp: Option<BwpR>,
+    pub pucch_config_common: Option<PucchConfigCommonR>,
     pub pucch_resource_set: Option<Vec<PucchResourceSetR>>,
     pub pucch_resource: Option<Vec<PucchResourceR>>,
     pub pucch_format1: PucchFormatConfigR,
@@ -36,7 +53,7 @@ pub struct PucchResourceSetR {
     pub max_payload_minus_1: Option<u32>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PucchResourceR {
     pub pucch_resource_id: u32,
     pub start_prb: u32,
@@ -111,9 +128,29 @@ impl Default for PucchFormatConfigR {
     }
 }
 
+impl Default for BwpGenericParametersR {
+    fn default() -> Self {
+        Self { subcarrier_spacing: 15, cyclic_prefix: CyclicPrefix::Normal }
+    }
+}
+
+impl Default for BwpR {
+    fn default() -> Self {
+        Self { start_crb: 0, num_rb: 106, generic_parameters: BwpGenericParametersR::default() }
+    }
+}
+
+impl Default for PucchConfigCommonR {
+    fn default() -> Self {
+        Self { index: 0, pucch_group_seq_hopping: PucchGroupSeqHopping::Neither, p0_nominal: -90 }
+    }
+}
+
 impl Default for PucchConfigR {
     fn default() -> Self {
         PucchConfigR {
+            bwp: Some(BwpR::default()),
+            pucch_config_common: Some(PucchConfigCommonR::default()),
             pucch_resource_set: Some(vec![PucchResourceSetR {
                 pucch_resource_set_id: 0,
                 pucch_resource_id: vec![0, 1],
@@ -0,0 +1,341 @@
+use crate::complex::*;
+use crate::constants::*;
+use crate::gold::*;
+use crate::rrc_pucch::{PucchFormat, PucchGroupSeqHopping};
+use std::f32::consts::PI;
+
+const NUM_SEQ_GROUP: u32 = 30;
+// 38.211, Table 5.2.2.2-1, phi(n) for M_ZC = 6
+#[rustfmt::skip]
+const LOW_PAPR_PHI_6: [[i8; 6]; 30] = [
+    [-3, -1, 3, 3, -1, -3],
+    [-3, 3, -1, -1, 3, -3],
+    [-3, -3, -3, 3, 1, -3],
+    [1, 1, 1, 3, -1, -3],
+    [1, 1, 1, -3, -1, 3],
+    [-3, 1, -1, -3, -3, -3],
+    [-3, 1, 3, -3, -3, -3],
+    [-3, -1, 1, -3, 1, -1],
+    [-3, -1, -3, 1, -3, -3],
+    [-3, -3, 1, -3, 3, -3],
+    [-3, 1, 3, 1, -3, -3],
+    [-3, -1, -3, 1, 1, -3],
+    [1, 1, 3, -1, -3, 3],
+    [1, 1, 3, 3, -1, 3],
+    [1, 1, 1, -3, 3, -1],
+    [1, 1, 1, -1, 3, -3],
+    [-3, -1, -1, -1, 3, -1],
+    [-3, -3, -1, 1, -1, -3],
+    [-3, -3, -3, 1, -3, -1],
+    [-3, 1, 1, -3, -1, -3],
+    [-3, 3, -3, 1, 1, -3],
+    [-3, 1, -3, -3, -3, -1],
+    [1, 1, -3, 3, 1, 3],
+    [1, 1, -3, -3, 1, -3],
+    [1, 1, 3, -1, 3, 3],
+    [1, 1, -3, 1, 3, 3],
+    [1, 1, -1, -1, 3, -1],
+    [1, 1, -1, 3, -1, -1],
+    [1, 1, -1, 3, -3, -1],
+    [1, 1, -3, 1, -1, -1],
+];
+
+// 38.211, Table 5.2.2.2-2, phi(n) for M_ZC = 12
+#[rustfmt::skip]
+const LOW_PAPR_PHI_12: [[i8; 12]; 30] = [
+    [-3, 1, -3, -3, -3, 3, -3, -1, 1, 1, 1, -3],
+    [-3, 3, 1, -3, 1, 3, -1, -1, 1, 3, 3, 3],
+    [-3, 3, 3, 1, -3, 3, -1, 1, 3, -3, 3, -3],
+    [-3, -3, -1, 3, 3, 3, -3, 3, -3, 1, -1, -3],
+    [-3, -1, -1, 1, 3, 1, 1, -1, 1, -1, -3, 1],
+    [-3, -3, 3, 1, -3, -3, -3, -1, 3, -1, 1, 3],
+    [1, -1, 3, -1, -1, -1, -3, -1, 1, 1, 1, -3],
+    [-1, -3, 3, -1, -3, -3, -3, -1, 1, -1, 1, -3],
+    [-3, -1, 3, 1, -3, -1, -3, 3, 1, 3, 3, 1],
+    [-3, -1, -1, -3, -3, -1, -3, 3, 1, 3, -1, -3],
+    [-3, 3, -3, 3, 3, -3, -1, -1, 3, 3, 1, -3],
+    [-3, -1, -3, -1, -1, -3, 3, 3, -1, -1, 1, -3],
+    [-3, -1, 3, -3, -3, -1, -3, 1, -1, -3, 3, 3],
+    [-3, 1, -1, -1, 3, 3, -3, -1, -1, -3, -1, -3],
+    [1, 3, -3, 1, 3, 3, 3, 1, -1, 1, -1, 3],
+    [-3, 1, 3, -1, -1, -3, -3, -1, -1, 3, 1, -3],
+    [-1, -1, -1, -1, 1, -3, -1, 3, 3, -1, -3, 1],
+    [-1, 1, 1, -1, 1, 3, 3, -1, -1, -3, 1, -3],
+    [-3, 1, 3, 3, -1, -1, -3, 3, 3, -3, 3, -3],
+    [-3, -3, 3, -3, -1, 3, 3, 3, -1, -3, 1, -3],
+    [3, 1, 3, 1, 3, -3, -1, 1, 3, 1, -1, -3],
+    [-3, 3, 1, 3, -3, 1, 1, 1, 1, 3, -3, 3],
+    [-3, 3, 3, 3, -1, -3, -3, -1, -3, 1, 3, -3],
+    [3, -1, -3, 3, -3, -1, 3, 3, 3, -3, -1, -3],
+    [-3, -1, 1, -3, 1, 3, 3, 3, -1, -3, 3, 3],
+    [-3, 3, 1, -1, 3, 3, -3, 1, -1, 1, -1, 1],
+    [-1, 1, 3, -3, 1, -1, 1, -1, -1, -3, 1, -1],
+    [-3, -3, 3, 3, 3, -3, -1, 1, -3, 3, 1, -3],
+    [1, -1, 3, 1, 1, -1, -1, -1, 1, 3, -3, 1],
+    [-3, 3, -3, 3, -3, -3, 3, -1, -1, 1, 3, -3],
+];
+
+// 38.211, Table 5.2.2.2-3, phi(n) for M_ZC = 18
+#[rustfmt::skip]
+const LOW_PAPR_PHI_18: [[i8; 18]; 30] = [
+    [-1, 3, -1, -3, 3, 1, -3, -1, 3, -3, -1, -1, 1, 1, 1, -1, -1, -1],
+    [3, -3, 3, -1, 1, 3, -3, -1, -3, -3, -1, -3, 3, 1, -1, 3, -3, 3],
+    [-3, 3, 1, -1, -1, 3, -3, -1, 1, 1, 1, 1, 1, -1, 3, -1, -3, -1],
+    [-3, -3, 3, 3, 3, 1, -3, 1, 3, 3, 1, -3, -3, 3, -1, -3, -1, 1],
+    [1, 1, -1, -1, -3, -1, 1, -3, -3, -3, 1, -3, -1, -1, 1, -1, 3, 1],
+    [3, -3, 1, 1, 3, -1, 1, -1, -1, -3, 1, 1, -1, 3, 3, -3, 3, -1],
+    [-3, 3, -1, 1, 3, 1, -3, -1, 1, 1, -3, 1, 3, 3, -1, -3, -3, -3],
+    [1, 1, -3, 3, 3, 1, 3, -3, 3, -1, 1, 1, -1, 1, -3, -3, -1, 3],
+    [-3, 1, -3, -3, 1, -3, -3, 3, 1, -3, -1, -3, -3, -3, -1, 1, 1, 3],
+    [3, -1, 3, 1, -3, -3, -1, 1, -3, -3, 3, 3, 3, 1, 3, -3, 3, -3],
+    [-3, -3, -3, 1, -3, 3, 1, 1, 3, -3, -3, 1, 3, -1, 3, -3, -3, 3],
+    [-3, -3, 3, 3, 3, -1, -1, -3, -1, -1, -1, 3, 1, -3, -3, -1, 3, -1],
+    [-3, -1, -3, -3, 1, 1, -1, -3, -1, -3, -1, -1, 3, 3, -1, 3, 1, 3],
+    [1, 1, -3, -3, -3, -3, 1, 3, -3, 3, 3, 1, -3, -1, 3, -1, -3, 1],
+    [-3, 3, -1, -3, -1, -3, 1, 1, -3, -3, -1, -1, 3, -3, 1, 3, 1, 1],
+    [3, 1, -3, 1, -3, 3, 3, -1, -3, -3, -1, -3, -3, 3, -3, -1, 1, 3],
+    [-3, -1, -3, -1, -3, 1, 3, -3, -1, 3, 3, 3, 1, -1, -3, 3, -1, -3],
+    [-3, -1, 3, 3, -1, 3, -1, -3, -1, 1, -1, -3, -1, -1, -1, 3, 3, 1],
+    [-3, 1, -3, -1, -1, 3, 1, -3, -3, -3, -1, -3, -3, 1, 1, 1, -1, -1],
+    [3, 3, 3, -3, -1, -3, -1, 3, -1, 1, -1, -3, 1, -3, -3, -1, 3, 3],
+    [-3, 1, 1, -3, 1, 1, 3, -3, -1, -3, -1, 3, -3, 3, -1, -1, -1, -3],
+    [1, -3, -1, -3, 3, 3, -1, -3, 1, -3, -3, -1, -3, -1, 1, 3, 3, 3],
+    [-3, -3, 1, -1, -1, 1, 1, -3, -1, 3, 3, 3, 3, -1, 3, 1, 3, 1],
+    [3, -1, -3, 1, -3, -3, -3, 3, 3, -1, 1, -3, -1, 3, 1, 1, 3, 3],
+    [3, -1, -1, 1, -3, -1, -3, -1, -3, -3, -1, -3, 1, 1, 1, -3, -3, 3],
+    [-3, -3, 1, -3, 3, 3, 3, -1, 3, 1, 1, -3, -3, -3, 3, -3, -1, -1],
+    [-3, -1, -1, -3, 1, -3, 3, -1, -1, -3, 3, 3, -3, -1, 3, -1, -1, -1],
+    [-3, -3, 3, 3, -3, 1, 3, -1, -3, 1, -1, -3, 3, -3, -1, -1, -1, 3],
+    [-1, -3, 1, -3, -3, -3, 1, 1, 3, 3, -3, 3, 3, -3, -1, 3, -3, 1],
+    [-3, 3, 1, -1, -1, -1, -1, 1, -1, 3, 3, -3, -1, 1, 3, -1, 3, -1],
+];
+
+// 38.211, Table 5.2.2.2-4, phi(n) for M_ZC = 24
+#[rustfmt::skip]
+const LOW_PAPR_PHI_24: [[i8; 24]; 30] = [
+    [-1, -3, 3, -1, 3, 1, 3, -1, 1, -3, -1, -3, -1, 1, 3, -3, -1, -3, 3, 3, 3, -3, -3, -3],
+    [-1, -3, 3, 1, 1, -3, 1, -3, -3, 1, -3, -1, -1, 3, -3, 3, 3, 3, -3, 1, 3, 3, -3, -3],
+    [-1, -3, -3, 1, -1, -1, -3, 1, 3, -1, -3, -1, -1, -3, 1, 1, 3, 1, -3, -1, -1, 3, -3, -3],
+    [1, -3, 3, -1, -3, -1, 3, 3, 1, -1, 1, 1, 3, -3, -1, -3, -3, -3, -1, 3, -3, -1, -3, -3],
+    [-1, 3, -3, -3, -1, 3, -1, -1, 1, 3, 1, 3, -1, -1, -3, 1, 3, 1, -1, -3, 1, -1, -3, -3],
+    [-3, -1, 1, -3, -3, 1, 1, -3, 3, -1, -1, -3, 1, 3, 1, -1, -3, -1, -3, 1, -3, -3, -3, -3],
+    [-3, 3, 1, 3, -1, 1, -3, 1, -3, 1, -1, -3, -1, -3, -3, -3, -3, -1, -1, -1, 1, 1, -3, -3],
+    [-3, 1, 3, -1, 1, -1, 3, -3, 3, -1, -3, -1, -3, 3, -1, -1, -1, -3, -1, -1, -3, 3, 3, -3],
+    [-3, 1, -3, 3, -1, -1, -1, -3, 3, 1, -1, -3, -1, 1, 3, -1, 1, -1, 1, -3, -3, -3, -3, -3],
+    [1, 1, -1, -3, -1, 1, 1, -3, 1, -1, 1, -3, 3, -3, -3, 3, -1, -3, 1, 3, -3, 1, -3, -3],
+    [-3, -3, -3, -1, 3, -3, 3, 1, 3, 1, -3, -1, -1, -3, 1, 1, 3, 1, -1, -3, 3, 1, 3, -3],
+    [-3, 3, -1, 3, 1, -1, -1, -1, 3, 3, 1, 1, 1, 3, 3, 1, -3, -3, -1, 1, -3, 1, 3, -3],
+    [3, -3, 3, -1, -3, 1, 3, 1, -1, -1, -3, -1, 3, -3, 3, -1, -1, 3, 3, -3, -3, 3, -3, -3],
+    [-3, 3, -1, 3, -1, 3, 3, 1, 1, -3, 1, 3, -3, 3, -3, -3, -1, 1, 3, -3, -1, -1, -3, -3],
+    [-3, 1, -3, -1, -1, 3, 1, 3, -3, 1, -1, 3, 3, -1, -3, 3, -3, -1, -1, -3, -3, -3, 3, -3],
+    [-3, -1, -1, -3, 1, -3, -3, -1, -1, 3, -1, 1, -1, 3, 1, -3, -1, 3, 1, 1, -1, -1, -3, -3],
+    [-3, -3, 1, -1, 3, 3, -3, -1, 1, -1, -1, 1, 1, -1, -1, 3, -3, 1, -3, 1, -1, -1, -1, -3],
+    [3, -1, 3, -1, 1, -3, 1, 1, -3, -3, 3, -3, -1, -1, -1, -1, -1, -3, -3, -1, 1, 1, -3, -3],
+    [-3, 1, -3, 1, -3, -3, 1, -3, 1, -3, -3, -3, -3, -3, 1, -3, -3, 1, 1, -3, 1, 1, -3, -3],
+    [-3, -3, 3, 3, 1, -1, -1, -1, 1, -3, -1, 1, -1, 3, -3, -1, -3, -1, -1, 1, -3, 3, -1, -3],
+    [-3, -3, -1, -1, -1, -3, 1, -1, -3, -1, 3, -3, 1, -3, 3, -3, 3, 3, 1, -1, -1, 1, -3, -3],
+    [3, -1, 1, -1, 3, -3, 1, 1, 3, -1, -3, 3, 1, -3, 3, -1, -1, -1, -1, 1, -3, -3, -3, -3],
+    [-3, 1, -3, 3, -3, 1, -3, 3, 1, -1, -3, -1, -3, -3, -3, -3, 1, 3, -1, 1, 3, 3, 3, -3],
+    [-3, -1, 1, -3, -1, -1, 1, 1, 1, 3, 3, -1, 1, -1, 1, -1, -1, -3, -3, -3, 3, 1, -1, -3],
+    [-3, 3, -1, -3, -1, -1, -1, 3, -1, -1, 3, -3, -1, 3, -3, 3, -3, -1, 3, 1, 1, -1, -3, -3],
+    [-3, 1, -1, -3, -3, -1, 1, -3, -1, -3, 1, 1, -1, 1, 1, 3, 3, 3, -1, 1, -1, 1, -1, -3],
+    [-1, 3, -1, -1, 3, 3, -1, -1, -1, 3, -1, -3, 1, 3, 1, 1, -3, -3, -3, -1, -3, -1, -3, -3],
+    [3, -3, -3, -1, 3, 3, -3, -1, 3, 1, 1, 1, 3, -1, 3, -3, -1, 3, -1, 3, 1, -1, -3, -3],
+    [-3, 1, -3, 1, -3, 1, 1, 3, 1, -3, -3, -1, 1, 3, -1, -3, 3, 1, -1, -3, -3, -3, -3, -3],
+    [3, -3, -1, 1, 3, -1, -1, -3, -1, 3, -1, -3, -1, -3, 3, -1, 3, 1, 1, -3, 3, -3, -3, -3],
+];
+
+// 38.211, 5.2.2, r_(u,v)^(alpha)(n) = e^(j alpha n) r_bar_(u,v)(n), 0 <= n < m_zc
+pub fn low_papr_seq(u: u32, v: u32, alpha: f32, m_zc: usize) -> Vec<Complex> {
+    low_papr_base_seq(u, v, m_zc).iter().enumerate().map(|(n, &r)| Complex::expj(alpha * n as f32) * r).collect()
+}
+
+// 38.211, 5.2.2.1 and 5.2.2.2, base sequence r_bar_(u,v)(n)
+pub fn low_papr_base_seq(u: u32, v: u32, m_zc: usize) -> Vec<Complex> {
+    assert!(u < NUM_SEQ_GROUP, "invalid sequence group {}!", u);
+    fn from_phi(phi: &[i8]) -> Vec<Complex> {
+        phi.iter().map(|&phi| Complex::expj(phi as f32 * PI / 4f32)).collect()
+    }
+
+    match m_zc {
+        6 => from_phi(&LOW_PAPR_PHI_6[u as usize]),
+        12 => from_phi(&LOW_PAPR_PHI_12[u as usize]),
+        18 => from_phi(&LOW_PAPR_PHI_18[u as usize]),
+        24 => from_phi(&LOW_PAPR_PHI_24[u as usize]),
+        30 => (0..m_zc as u64)
+            .map(|n| {
+                let phase = ((u as u64 + 1) * (n + 1) * (n + 2)) % 62;
+                Complex::expj(-PI * phase as f32 / 31f32)
+            })
+            .collect(),
+        _ if m_zc >= 36 => {
+            assert!(v <= 1, "invalid base sequence number {}!", v);
+            // two base sequences per group only for M_ZC >= 6 * N_sc^RB
+            let v = if m_zc >= (6 * NUM_SC_PER_RB) as usize { v } else { 0 };
+            let n_zc = (2..m_zc).rev().find(|&n| is_prime(n)).unwrap() as u64;
+            // q_bar = N_ZC * (u + 1) / 31, q = floor(q_bar + 1/2) + v * (-1)^floor(2 * q_bar)
+            let q_bar_x31 = n_zc * (u as u64 + 1);
+            let q = (2 * q_bar_x31 + 31) / 62;
+            let q = match (v, (2 * q_bar_x31 / 31) % 2) {
+                (0, _) => q,
+                (_, 0) => q + 1,
+                _ => q - 1,
+            };
+            (0..m_zc as u64)
+                .map(|n| {
+                    let m = n % n_zc;
+                    let phase = (q * m * (m + 1)) % (2 * n_zc);
+                    Complex::expj(-PI * phase as f32 / n_zc as f32)
+                })
+                .collect()
+        }
+        _ => panic!("invalid low PAPR sequence length {}!", m_zc),
+    }
+}
+
+fn is_prime(n: usize) -> bool {
+    n >= 2 && (2..).take_while(|i| i * i <= n).all(|i| !n.is_multiple_of(i))
+}
+
+// 38.211, 6.3.2.2.1, n_ID is hoppingId if configured, otherwise the physical cell id
+pub fn pucch_hopping_id(group_seq_hopping: &PucchGroupSeqHopping, cell_id: u32) -> u32 {
+    match group_seq_hopping {
+        PucchGroupSeqHopping::Neither => cell_id,
+        PucchGroupSeqHopping::GroupHopping(hopping_id) | PucchGroupSeqHopping::SeqHopping(hopping_id) => *hopping_id,
+    }
+}
+
+// 38.211, 6.3.2.2.1, (u, v) in slot n_s, n_hop is 0 for the first hop or no hopping, 1 for the second hop
+pub fn pucch_group_seq(group_seq_hopping: &PucchGroupSeqHopping, cell_id: u32, slot: u32, n_hop: u32) -> (u32, u32) {
+    let n_id = pucch_hopping_id(group_seq_hopping, cell_id);
+    let f_ss = n_id % NUM_SEQ_GROUP;
+    match group_seq_hopping {
+        PucchGroupSeqHopping::Neither => (f_ss, 0),
+        PucchGroupSeqHopping::GroupHopping(_) => {
+            let c = gold_seq(c_init_group_hopping(n_id), (8 * (2 * slot + n_hop)) as usize, 8);
+            let f_gh = (0..8).map(|m| (c[m] as u32) << m).sum::<u32>() % NUM_SEQ_GROUP;
+            ((f_gh + f_ss) % NUM_SEQ_GROUP, 0)
+        }
+        PucchGroupSeqHopping::SeqHopping(_) => {
+            let c = gold_seq(c_init_seq_hopping(n_id), (2 * slot + n_hop) as usize, 1);
+            (f_ss, c[0] as u32)
+        }
+    }
+}
+
+// 38.211, 6.3.2.2.2, n_cs(n_s, l) with l counted from the first symbol of the slot
+fn pucch_n_cs(n_id: u32, num_sym_per_slot: u32, slot: u32, sym: u32) -> u32 {
+    let c = gold_seq(c_init_cyclic_shift(n_id), (8 * num_sym_per_slot * slot + 8 * sym) as usize, 8);
+    (0..8).map(|m| (c[m] as u32) << m).sum()
+}
+
+// 38.211, 6.3.2.3.1, 6.3.2.4.1 and Table 6.4.1.3.3.1-1, m_0 of the PUCCH format
+fn pucch_m0(format: &PucchFormat) -> u32 {
+    match *format {
+        PucchFormat::Format0 { init_cyclic_shift, .. } | PucchFormat::Format1 { init_cyclic_shift, .. } => init_cyclic_shift,
+        PucchFormat::Format3 { .. } => 0,
+        PucchFormat::Format4 { occ_len, occ_idx, .. } => {
+            const M0_OCC_LEN_2: [u32; 2] = [0, 6];
+            const M0_OCC_LEN_4: [u32; 4] = [0, 6, 3, 9];
+            match occ_len {
+                2 => M0_OCC_LEN_2[occ_idx as usize],
+                4 => M0_OCC_LEN_4[occ_idx as usize],
+                _ => panic!("invalid occ length {}!", occ_len),
+            }
+        }
+        PucchFormat::Format2 { .. } => panic!("impossible to be here!"),
+    }
+}
+
+// 38.213, Table 9.2.3-3 and Table 9.2.3-4, m_CS of PUCCH format 0
+// harq_ack is empty for positive SR only
+pub fn format0_m_cs(harq_ack: &[u8], sr: bool) -> u32 {
+    match (harq_ack, sr) {
+        ([], true) => 0,
+        ([ack], false) => [0, 6][*ack as usize],
+        ([ack], true) => [3, 9][*ack as usize],
+        ([ack0, ack1], false) => [[0, 3], [9, 6]][*ack0 as usize][*ack1 as usize],
+        ([ack0, ack1], true) => [[1, 4], [10, 7]][*ack0 as usize][*ack1 as usize],
+        _ => panic!("invalid PUCCH format 0 payload {:?}, SR {}!", harq_ack, sr),
+    }
+}
+
+// the (harq_ack, sr) of PUCCH format 0 with o_harq HARQ-ACK bits, and the positive SR ones if sr
+pub fn format0_uci_candidates(o_harq: u32, sr: bool) -> Vec<(Vec<u8>, bool)> {
+    let harq_acks = (0..1u32 << o_harq).map(|v| (0..o_harq).map(|i| ((v >> i) & 1) as u8).collect::<Vec<_>>());
+    let mut candidates = Vec::new();
+    for harq_ack in harq_acks {
+        if !harq_ack.is_empty() {
+            candidates.push((harq_ack.clone(), false));
+        }
+        if sr {
+            candidates.push((harq_ack, true));
+        }
+    }
+    candidates
+}
+
+// 38.211, 6.3.2.2.2, alpha_l of symbol l in slot n_s, l is counted from the first PUCCH symbol
+// m_cs is 0 except for format 0, n_id is from pucch_hopping_id
+pub fn pucch_alpha(format: &PucchFormat, n_id: u32, num_sym_per_slot: u32, slot: u32, l: u32, m_cs: u32) -> f32 {
+    let start_sym = match *format {
+        PucchFormat::Format0 { start_sym, .. }
+        | PucchFormat::Format1 { start_sym, .. }
+        | PucchFormat::Format3 { start_sym, .. }
+        | PucchFormat::Format4 { start_sym, .. } => start_sym,
+        PucchFormat::Format2 { .. } => panic!("impossible to be here!"),
+    };
+    let n_cs = pucch_n_cs(n_id, num_sym_per_slot, slot, l + start_sym);
+    2f32 * PI / NUM_SC_PER_RB as f32 * ((pucch_m0(format) + m_cs + n_cs) % NUM_SC_PER_RB) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn low_papr_seq_property() {
+        for m_zc in [6, 12, 18, 24, 30, 36, 48, 72, 144] {
+            for u in 0..NUM_SEQ_GROUP {
+                let seq = low_papr_seq(u, 0, PI / 6f32, m_zc);
+                assert_eq!(seq.len(), m_zc);
+                assert!(seq.iter().all(|r| (r.abs() - 1f32).abs() < 1e-5));
+            }
+        }
+
+        // cyclic shifts of the same base sequence are orthogonal
+        let seq0 = low_papr_seq(3, 0, 0f32, 12);
+        let seq1 = low_papr_seq(3, 0, 2f32 * PI * 5f32 / 12f32, 12);
+        let corr: Complex = seq0.iter().zip(&seq1).map(|(&a, &b)| a * b.conj()).sum();
+        assert!(corr.abs() < 1e-4);
+    }
+
+    #[test]
+    fn group_seq_hopping() {
+        assert_eq!(pucch_group_seq(&PucchGroupSeqHopping::Neither, 65, 7, 1), (5, 0));
+
+        let hopping = PucchGroupSeqHopping::GroupHopping(500);
+        let groups = (0..20).map(|slot| pucch_group_seq(&hopping, 0, slot, 0)).collect::<Vec<_>>();
+        assert!(groups.iter().all(|&(u, v)| u < NUM_SEQ_GROUP && v == 0));
+        assert!(groups.iter().any(|&group| group != groups[0]));
+
+        let hopping = PucchGroupSeqHopping::SeqHopping(500);
+        let seqs = (0..20).map(|slot| pucch_group_seq(&hopping, 0, slot, 1)).collect::<Vec<_>>();
+        assert!(seqs.iter().all(|&(u, v)| u == 500 % NUM_SEQ_GROUP && v <= 1));
+    }
+
+    #[test]
+    fn cyclic_shift() {
+        assert_eq!(format0_m_cs(&[1], false), 6);
+        assert_eq!(format0_m_cs(&[1, 0], false), 9);
+        assert_eq!(format0_m_cs(&[0, 1], true), 4);
+
+        let format = PucchFormat::Format0 { init_cyclic_shift: 2, num_sym: 2, start_sym: 12 };
+        for l in 0..2 {
+            let alpha_ack = pucch_alpha(&format, 100, 14, 3, l, format0_m_cs(&[1], false));
+            let alpha_nack = pucch_alpha(&format, 100, 14, 3, l, format0_m_cs(&[0], false));
+            assert!((0f32..2f32 * PI).contains(&alpha_ack));
+            assert!(((alpha_ack - alpha_nack).rem_euclid(2f32 * PI) - PI).abs() < 1e-5);
+        }
+    }
+}
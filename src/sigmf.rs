@@ -0,0 +1,156 @@
+use crate::complex::*;
+use crate::err::Error;
+use crate::ofdm::*;
+use serde_derive::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufWriter;
+
+const SIGMF_VERSION: &str = "1.0.0";
+const SIGMF_DATATYPE: &str = "cf32_le";
+const SIGMF_DATA_EXT: &str = "sigmf-data";
+const SIGMF_META_EXT: &str = "sigmf-meta";
+// the namespace of the PUCCH fields of the annotations
+const NR_EXTENSION_NAME: &str = "nr";
+const NR_EXTENSION_VERSION: &str = "1.0.0";
+
+// SigMF metadata, https://github.com/sigmf/SigMF, the PUCCH fields are in the "nr" namespace
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SigmfMeta {
+    pub global: SigmfGlobal,
+    pub captures: Vec<SigmfCapture>,
+    pub annotations: Vec<SigmfAnnotation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SigmfGlobal {
+    #[serde(rename = "core:datatype")]
+    pub datatype: String,
+    #[serde(rename = "core:sample_rate")]
+    pub sample_rate: f64,
+    #[serde(rename = "core:version")]
+    pub version: String,
+    #[serde(rename = "core:description", default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "core:extensions", default)]
+    pub extensions: Vec<SigmfExtension>,
+}
+
+// a namespace used by the recording, other than "core"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SigmfExtension {
+    pub name: String,
+    pub version: String,
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SigmfCapture {
+    #[serde(rename = "core:sample_start")]
+    pub sample_start: u64,
+    #[serde(rename = "core:frequency")]
+    pub frequency: f64,
+}
+
+// one PUCCH transmission
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SigmfAnnotation {
+    #[serde(rename = "core:sample_start")]
+    pub sample_start: u64,
+    #[serde(rename = "core:sample_count")]
+    pub sample_count: u64,
+    #[serde(rename = "core:label", default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(rename = "nr:slot")]
+    pub slot: u32,
+    #[serde(rename = "nr:pucch_resource_id")]
+    pub pucch_resource_id: u32,
+    #[serde(rename = "nr:uci")]
+    pub uci: Vec<u8>, // UCI bits before channel coding
+}
+
+impl SigmfMeta {
+    pub fn new(sample_rate: f64, center_freq_hz: f64) -> Self {
+        SigmfMeta {
+            global: SigmfGlobal {
+                datatype: SIGMF_DATATYPE.to_string(),
+                sample_rate,
+                version: SIGMF_VERSION.to_string(),
+                description: None,
+                extensions: vec![SigmfExtension {
+                    name: NR_EXTENSION_NAME.to_string(),
+                    version: NR_EXTENSION_VERSION.to_string(),
+                    optional: true,
+                }],
+            },
+            captures: vec![SigmfCapture { sample_start: 0, frequency: center_freq_hz }],
+            annotations: Vec::new(),
+        }
+    }
+
+    pub fn add_annotation(&mut self, annotation: SigmfAnnotation) {
+        self.annotations.push(annotation);
+        self.annotations.sort_by_key(|annotation| annotation.sample_start);
+    }
+}
+
+fn sigmf_file_name(base_name: &str, ext: &str) -> String {
+    format!("{}.{}", base_name, ext)
+}
+
+// base_name.sigmf-data and base_name.sigmf-meta
+pub fn write_sigmf(base_name: &str, meta: &SigmfMeta, samples: &[Complex]) -> Result<(), Error> {
+    write_cf32(&sigmf_file_name(base_name, SIGMF_DATA_EXT), samples)?;
+    let f = BufWriter::new(File::create(sigmf_file_name(base_name, SIGMF_META_EXT))?);
+    serde_json::to_writer_pretty(f, meta).map_err(|_| Error::Nr("cannot serialize SigMF meta!"))?;
+    Ok(())
+}
+
+pub fn read_sigmf(base_name: &str) -> Result<(SigmfMeta, Vec<Complex>), Error> {
+    let f = File::open(sigmf_file_name(base_name, SIGMF_META_EXT))?;
+    let meta: SigmfMeta = serde_json::from_reader(f).map_err(|_| Error::Nr("invalid SigMF meta!"))?;
+    if meta.global.datatype != SIGMF_DATATYPE {
+        return Err(Error::Nr("unsupported SigMF datatype!"));
+    }
+    let samples = read_cf32(&sigmf_file_name(base_name, SIGMF_DATA_EXT))?;
+    Ok((meta, samples))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sigmf_loopback() {
+        let mut meta = SigmfMeta::new(30.72e6, 3.5e9);
+        meta.add_annotation(SigmfAnnotation {
+            sample_start: 30720,
+            sample_count: 30720,
+            label: Some("PUCCH format 3".to_string()),
+            slot: 1,
+            pucch_resource_id: 3,
+            uci: vec![1, 0, 1, 1],
+        });
+        meta.add_annotation(SigmfAnnotation {
+            sample_start: 0,
+            sample_count: 4384,
+            label: None,
+            slot: 0,
+            pucch_resource_id: 0,
+            uci: vec![1],
+        });
+        let samples = (0..61440).map(|n| Complex::expj(n as f32 * 0.01)).collect::<Vec<_>>();
+
+        let base_name = std::env::temp_dir().join(format!("nr_sigmf_{}", std::process::id()));
+        let base_name = base_name.to_str().unwrap();
+        write_sigmf(base_name, &meta, &samples).unwrap();
+        let (rx_meta, rx_samples) = read_sigmf(base_name).unwrap();
+        std::fs::remove_file(sigmf_file_name(base_name, SIGMF_DATA_EXT)).unwrap();
+        std::fs::remove_file(sigmf_file_name(base_name, SIGMF_META_EXT)).unwrap();
+
+        assert_eq!(rx_meta, meta);
+        let extension = &rx_meta.global.extensions[0];
+        assert_eq!((extension.name.as_str(), extension.version.as_str(), extension.optional), ("nr", "1.0.0", true));
+        assert_eq!(rx_meta.annotations[0].pucch_resource_id, 0);
+        assert_eq!(rx_samples, samples);
+    }
+}
@@ -0,0 +1,59 @@
+use crate::pucch::PucchConfig;
+use crate::pucch_tx::PucchTxParams;
+use crate::read_config::load_config;
+use crate::rrc_pucch::*;
+
+pub const TEST_PUCCH_CONFIG_FILE: &str = "input/pucch_config.json";
+
+// the test PUCCH configuration with extra resources appended to its resource list
+pub fn test_pucch_config_rrc(extra: &[PucchResourceR]) -> PucchConfigR {
+    let mut pucch_config_rrc = load_config::<PucchConfigR>(TEST_PUCCH_CONFIG_FILE).unwrap();
+    pucch_config_rrc.pucch_resource.get_or_insert_with(Vec::new).extend_from_slice(extra);
+    pucch_config_rrc
+}
+
+pub fn test_pucch_config(extra: &[PucchResourceR]) -> PucchConfig {
+    test_pucch_config_rrc(extra).into()
+}
+
+// format 0 .. 4 resources beside the ones of the test configuration, 11 / 12 and 13 / 14 differ only by the OCC
+pub fn test_pucch_resources() -> Vec<PucchResourceR> {
+    let mut resources = vec![PucchResourceR {
+        pucch_resource_id: 10,
+        start_prb: 5,
+        intra_slot_freq_hopping: IntraSlotFreqHopping::Hopping { second_prb: 30 },
+        format: PucchFormat::Format0 { init_cyclic_shift: 3, num_sym: 2, start_sym: 12 },
+    }];
+    for (id, time_occ) in [(11, 0), (12, 1)] {
+        resources.push(PucchResourceR {
+            pucch_resource_id: id,
+            start_prb: 8,
+            intra_slot_freq_hopping: IntraSlotFreqHopping::Hopping { second_prb: 30 },
+            format: PucchFormat::Format1 { init_cyclic_shift: 0, num_sym: 14, start_sym: 0, time_occ },
+        });
+    }
+    for (id, occ_idx) in [(13, 0), (14, 1)] {
+        resources.push(PucchResourceR {
+            pucch_resource_id: id,
+            start_prb: 40,
+            intra_slot_freq_hopping: IntraSlotFreqHopping::Hopping { second_prb: 3 },
+            format: PucchFormat::Format4 { num_sym: 11, occ_len: 2, occ_idx, start_sym: 2 },
+        });
+    }
+    resources.push(PucchResourceR {
+        pucch_resource_id: 15,
+        start_prb: 20,
+        intra_slot_freq_hopping: IntraSlotFreqHopping::NoHopping,
+        format: PucchFormat::Format3 { num_rb: 2, num_sym: 14, start_sym: 0 },
+    });
+    resources
+}
+
+pub fn test_params(slot: u32) -> PucchTxParams {
+    PucchTxParams { slot, cell_id: 1, n_rnti: 0x4601, data_scrambling_id: None, dmrs_scrambling_id: None }
+}
+
+// noise-free LLRs, positive for bit 0
+pub fn bits_to_llr(bits: &[u8]) -> Vec<f32> {
+    bits.iter().map(|&bit| if bit == 0 { 1f32 } else { -1f32 }).collect()
+}
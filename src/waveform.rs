@@ -0,0 +1,153 @@
+use crate::channel::Rng;
+use crate::constants::*;
+use crate::err::Error;
+use crate::grid::*;
+use crate::link_sim::*;
+use crate::numerology::SlotTime;
+use crate::ofdm::Ofdm;
+use crate::pucch::*;
+use crate::pucch_tx::PucchTxParams;
+use crate::sigmf::*;
+
+// the smallest power of 2 FFT holding the BWP
+fn fft_size(num_rb: u32) -> u32 {
+    (num_rb * NUM_SC_PER_RB).next_power_of_two().max(128)
+}
+
+// the carrier is centered on the BWP, f0 of the phase compensation
+fn carrier_ofdm(pucch_config: &PucchConfig, center_freq_hz: f64) -> Ofdm {
+    let bwp = &pucch_config.bwp;
+    Ofdm::new(bwp.numerology, fft_size(bwp.num_rb), bwp.num_rb, center_freq_hz)
+}
+
+// an annotated transmission of a recording and its decoded UCI, None for DTX
+#[derive(Debug, Clone)]
+pub struct DecodedTransmission {
+    pub annotation: SigmfAnnotation,
+    pub decoded: Option<Vec<u8>>,
+}
+
+impl DecodedTransmission {
+    pub fn is_correct(&self) -> bool {
+        self.decoded.as_ref() == Some(&self.annotation.uci)
+    }
+}
+
+// one transmission of the link sim resource per slot from SFN 0, alternating ACK and NACK, mapped to the BWP grid and
+// CP-OFDM modulated into base_name.sigmf-data / .sigmf-meta, the grid of the first slot is dumped to base_name.grid
+pub fn export_sigmf(
+    pucch_config: &PucchConfig,
+    params: PucchTxParams,
+    config: &LinkSimConfig,
+    num_slots: u32,
+    point_a_hz: f64,
+    base_name: &str,
+) -> Result<SigmfMeta, Error> {
+    let bwp = &pucch_config.bwp;
+    let center_freq_hz = bwp.sc_freq_hz(0, bwp.num_rb * NUM_SC_PER_RB / 2, point_a_hz);
+    let ofdm = carrier_ofdm(pucch_config, center_freq_hz);
+    let link_sim = LinkSim::new(pucch_config, params, config);
+    let pucch_resource = PucchResourceId::new(pucch_config, config.pucch_resource_id).pucch_resource(pucch_config);
+    let mut meta = SigmfMeta::new(ofdm.sample_rate(), center_freq_hz);
+    let mut rng = Rng::new(config.seed);
+    let mut grid = ResourceGrid::new(bwp.num_rb, ofdm.numerology().num_sym_per_slot());
+    let mut samples = Vec::new();
+    let mut slot_time = SlotTime::new(ofdm.numerology(), 0, 0);
+
+    for n in 0..num_slots {
+        let transmission = if n % 2 == 0 { Transmission::Ack } else { Transmission::Nack };
+        let uci = link_sim.uci(transmission, &mut rng);
+        let params = PucchTxParams { slot: slot_time.slot(), ..params };
+        grid.clear();
+        map_pucch(&mut grid, pucch_config, pucch_resource, 0, &link_sim.transmit(&params, &uci));
+        if n == 0 {
+            grid.dump(&format!("{}.grid", base_name))?;
+        }
+        let slot_samples = ofdm.modulate(&grid, slot_time.slot());
+        meta.add_annotation(SigmfAnnotation {
+            sample_start: samples.len() as u64,
+            sample_count: slot_samples.len() as u64,
+            label: Some(format!("PUCCH resource {} at {}", config.pucch_resource_id, slot_time)),
+            slot: slot_time.slot(),
+            pucch_resource_id: config.pucch_resource_id,
+            uci,
+        });
+        samples.extend(slot_samples);
+        slot_time = slot_time.add_slots(1);
+    }
+    write_sigmf(base_name, &meta, &samples)?;
+    Ok(meta)
+}
+
+// the receiver path of export_sigmf
+pub fn import_sigmf(
+    pucch_config: &PucchConfig,
+    params: PucchTxParams,
+    config: &LinkSimConfig,
+    base_name: &str,
+) -> Result<Vec<DecodedTransmission>, Error> {
+    let (meta, samples) = read_sigmf(base_name)?;
+    let center_freq_hz = meta.captures.first().map_or(0f64, |capture| capture.frequency);
+    let ofdm = carrier_ofdm(pucch_config, center_freq_hz);
+    if ofdm.sample_rate() != meta.global.sample_rate {
+        return Err(Error::Nr("SigMF sample rate does not match the BWP!"));
+    }
+    let link_sim = LinkSim::new(pucch_config, params, config);
+    let pucch_resource = PucchResourceId::new(pucch_config, config.pucch_resource_id).pucch_resource(pucch_config);
+
+    meta.annotations
+        .into_iter()
+        .map(|annotation| {
+            let (start, end) =
+                (annotation.sample_start as usize, (annotation.sample_start + annotation.sample_count) as usize);
+            if annotation.pucch_resource_id != config.pucch_resource_id || end > samples.len() {
+                return Err(Error::Nr("SigMF annotation does not match the recording!"));
+            }
+            let grid = ofdm.demodulate(&samples[start..end], annotation.slot);
+            let rx = extract_pucch(&grid, pucch_config, pucch_resource, 0);
+            let decoded = link_sim.receive(&PucchTxParams { slot: annotation.slot, ..params }, &[rx]);
+            Ok(DecodedTransmission { annotation, decoded })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::ChannelConfig;
+    use crate::combining::Combining;
+    use crate::test_util::*;
+
+    #[test]
+    fn sigmf_export_import() {
+        let pucch_config = test_pucch_config(&[]);
+        let base_name = std::env::temp_dir().join(format!("nr_waveform_{}", std::process::id()));
+        let base_name = base_name.to_str().unwrap();
+        // format 0 with 2 HARQ-ACK bits, format 2 with Reed-Muller and format 3 with polar coded UCI
+        for (pucch_resource_id, o_harq, o_csi) in [(0, 2, 0), (2, 2, 4), (3, 2, 20)] {
+            let config = LinkSimConfig {
+                pucch_resource_id,
+                o_harq,
+                o_csi,
+                channel: ChannelConfig::default(),
+                snr_db: Vec::new(),
+                num_trials: 0,
+                combining: Combining::Mrc,
+                dtx_threshold: 0.5,
+                seed: 1,
+            };
+            let meta = export_sigmf(&pucch_config, test_params(0), &config, 3, 3.5e9, base_name).unwrap();
+            let decoded = import_sigmf(&pucch_config, test_params(0), &config, base_name).unwrap();
+            for ext in ["sigmf-data", "sigmf-meta", "grid"] {
+                std::fs::remove_file(format!("{}.{}", base_name, ext)).unwrap();
+            }
+
+            assert_eq!(meta.annotations.len(), 3);
+            assert_eq!(
+                meta.annotations[2].label.as_deref(),
+                Some(format!("PUCCH resource {} at 0.2", pucch_resource_id).as_str())
+            );
+            assert!(decoded.iter().all(|transmission| transmission.is_correct()), "{:?}", decoded);
+        }
+    }
+}